@@ -0,0 +1,92 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::archive::Archive;
+use crate::auth::Auth;
+use crate::metrics::Metrics;
+use crate::net::clients::Clients;
+use crate::net::limits::RoomLimiter;
+use crate::net::outbox::QueueStats;
+use crate::room::actor::RoomServices;
+use crate::room::journal::Journal;
+use crate::room::manager::RoomManager;
+use crate::room::matchmaker::Matchmaker;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub manager: RoomManager,
+    pub matchmaker: Matchmaker,
+    pub archive: Archive,
+    pub journal: Journal,
+    pub auth: Option<Arc<Auth>>,
+    pub sockets: Arc<watch::Sender<usize>>,
+    pub queue_stats: Arc<QueueStats>,
+    pub room_limiter: RoomLimiter,
+    pub metrics: Metrics,
+    pub clients: Clients,
+    pub admin_token: Option<Arc<str>>,
+}
+
+impl AppState {
+    pub fn new(
+        archive: Archive,
+        journal: Journal,
+        auth: Option<Auth>,
+        admin_token: Option<String>,
+    ) -> Self {
+        let metrics = Metrics::new();
+        let services = RoomServices {
+            archive: archive.clone(),
+            journal: journal.clone(),
+            metrics: metrics.clone(),
+        };
+        let manager = RoomManager::new(services);
+        Self {
+            matchmaker: Matchmaker::spawn(manager.clone(), archive.clone()),
+            manager,
+            archive,
+            journal,
+            auth: auth.map(Arc::new),
+            sockets: Arc::new(watch::channel(0).0),
+            queue_stats: Arc::default(),
+            room_limiter: RoomLimiter::default(),
+            metrics,
+            clients: Clients::default(),
+            admin_token: admin_token.map(Arc::from),
+        }
+    }
+
+    pub fn resolve_invited(&self, name: Option<&str>) -> Result<Option<Uuid>, String> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        let auth = self
+            .auth
+            .as_deref()
+            .ok_or_else(|| "Invitations require player accounts".to_string())?;
+        auth.player_id(name)
+            .map(Some)
+            .ok_or_else(|| format!("No player named {}", name.trim()))
+    }
+
+    pub async fn wait_sockets_closed(&self) {
+        let mut sockets = self.sockets.subscribe();
+        let _ = sockets.wait_for(|n| *n == 0).await;
+    }
+
+    pub async fn flush_storage(&self, timeout: Duration) {
+        let archive = self.archive.clone();
+        let journal = self.journal.clone();
+        let flushed =
+            tokio::task::spawn_blocking(move || journal.flush(timeout) && archive.flush(timeout))
+                .await
+                .unwrap_or(false);
+
+        if !flushed {
+            tracing::warn!("Storage was not fully flushed before exit");
+        }
+    }
+}
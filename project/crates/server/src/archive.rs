@@ -0,0 +1,530 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::rating::{elo_delta, Ratings, DEFAULT_RATING};
+
+use shared::net::{LeaderboardEntry, Profile};
+use shared::types::{Action, GameState, GameStatus, Turn};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS rooms (
+    room_id     TEXT PRIMARY KEY,
+    name        TEXT NOT NULL,
+    config      TEXT NOT NULL,
+    created_at  INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS games (
+    game_id        TEXT PRIMARY KEY,
+    room_id        TEXT NOT NULL REFERENCES rooms(room_id),
+    trapper_id     TEXT NOT NULL,
+    mouse_id       TEXT NOT NULL,
+    initial_state  TEXT NOT NULL,
+    started_at     INTEGER NOT NULL,
+    ended_at       INTEGER,
+    status         TEXT,
+    winner         TEXT
+);
+
+CREATE TABLE IF NOT EXISTS actions (
+    game_id  TEXT NOT NULL REFERENCES games(game_id),
+    ply      INTEGER NOT NULL,
+    side     TEXT NOT NULL,
+    action   TEXT NOT NULL,
+    auto     INTEGER NOT NULL,
+    at       INTEGER NOT NULL,
+    PRIMARY KEY (game_id, ply)
+);
+
+CREATE TABLE IF NOT EXISTS players (
+    player_id   TEXT PRIMARY KEY,
+    name        TEXT NOT NULL,
+    created_at  INTEGER NOT NULL,
+    last_seen   INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS ratings (
+    player_id  TEXT NOT NULL,
+    role       TEXT NOT NULL,
+    rating     REAL NOT NULL,
+    games      INTEGER NOT NULL,
+    PRIMARY KEY (player_id, role)
+);
+";
+
+pub enum ArchiveEvent {
+    RoomCreated {
+        room_id: String,
+        name: String,
+        config: String,
+    },
+    GameStarted {
+        game_id: Uuid,
+        room_id: String,
+        trapper_id: Uuid,
+        mouse_id: Uuid,
+        state: GameState,
+    },
+    ActionApplied {
+        game_id: Uuid,
+        ply: u32,
+        side: Turn,
+        action: Action,
+        auto: bool,
+    },
+    GameEnded {
+        game_id: Uuid,
+        status: GameStatus,
+    },
+    PlayerSeen {
+        player_id: Uuid,
+        name: String,
+    },
+}
+
+enum ArchiveOp {
+    Record(ArchiveEvent),
+    Flush(mpsc::Sender<()>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActionRecord {
+    pub ply: u32,
+    pub side: Turn,
+    pub action: Action,
+    pub auto: bool,
+    pub at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameRecord {
+    pub game_id: String,
+    pub room_id: String,
+    pub room_name: String,
+    pub trapper_id: String,
+    pub mouse_id: String,
+    pub initial_state: GameState,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub status: Option<GameStatus>,
+    pub actions: Vec<ActionRecord>,
+}
+
+#[derive(Clone)]
+pub struct Archive {
+    ops_tx: mpsc::Sender<ArchiveOp>,
+    reader: Arc<Mutex<Connection>>,
+}
+
+impl Archive {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let writer = Connection::open(path)?;
+        writer.busy_timeout(std::time::Duration::from_secs(5))?;
+        writer.pragma_update(None, "journal_mode", "WAL")?;
+        writer.execute_batch(SCHEMA)?;
+
+        let reader = Connection::open(path)?;
+
+        let (ops_tx, ops_rx) = mpsc::channel();
+        std::thread::spawn(move || write_loop(writer, ops_rx));
+
+        Ok(Self {
+            ops_tx,
+            reader: Arc::new(Mutex::new(reader)),
+        })
+    }
+
+    pub fn record(&self, event: ArchiveEvent) {
+        if self.ops_tx.send(ArchiveOp::Record(event)).is_err() {
+            tracing::error!("Archive writer is gone, dropping event");
+        }
+    }
+
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let (done_tx, done_rx) = mpsc::channel();
+        self.ops_tx.send(ArchiveOp::Flush(done_tx)).is_ok() && done_rx.recv_timeout(timeout).is_ok()
+    }
+
+    pub async fn list_games(&self, limit: u32, offset: u32) -> Result<Vec<GameRecord>, String> {
+        let reader = self.reader.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = reader
+                .lock()
+                .map_err(|_| "Archive lock poisoned".to_string())?;
+            let ids = game_ids(&conn, limit, offset).map_err(|e| e.to_string())?;
+            ids.iter()
+                .filter_map(|id| load_game(&conn, id).transpose())
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    pub async fn get_profile(&self, player_id: String) -> Result<Option<Profile>, String> {
+        let reader = self.reader.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = reader
+                .lock()
+                .map_err(|_| "Archive lock poisoned".to_string())?;
+            load_profile(&conn, &player_id).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    pub async fn get_ratings(&self, player_id: String) -> Result<Ratings, String> {
+        let reader = self.reader.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = reader
+                .lock()
+                .map_err(|_| "Archive lock poisoned".to_string())?;
+            load_ratings(&conn, &player_id).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    pub async fn leaderboard(
+        &self,
+        role: Turn,
+        limit: u32,
+    ) -> Result<Vec<LeaderboardEntry>, String> {
+        let reader = self.reader.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = reader
+                .lock()
+                .map_err(|_| "Archive lock poisoned".to_string())?;
+            load_leaderboard(&conn, role, limit).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    pub async fn get_game(&self, game_id: String) -> Result<Option<GameRecord>, String> {
+        let reader = self.reader.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = reader
+                .lock()
+                .map_err(|_| "Archive lock poisoned".to_string())?;
+            load_game(&conn, &game_id).map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn to_json<T: Serialize>(v: &T) -> String {
+    serde_json::to_string(v).unwrap_or_default()
+}
+
+fn write_loop(conn: Connection, ops_rx: mpsc::Receiver<ArchiveOp>) {
+    while let Ok(op) = ops_rx.recv() {
+        match op {
+            ArchiveOp::Record(event) => {
+                if let Err(e) = write_event(&conn, event) {
+                    tracing::error!("Failed to archive event: {}", e);
+                }
+            }
+            ArchiveOp::Flush(done_tx) => {
+                let _ = done_tx.send(());
+            }
+        }
+    }
+}
+
+fn write_event(conn: &Connection, event: ArchiveEvent) -> rusqlite::Result<()> {
+    match event {
+        ArchiveEvent::RoomCreated {
+            room_id,
+            name,
+            config,
+        } => {
+            conn.execute(
+                "INSERT OR REPLACE INTO rooms (room_id, name, config, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![room_id, name, config, now_ms()],
+            )?;
+        }
+        ArchiveEvent::GameStarted {
+            game_id,
+            room_id,
+            trapper_id,
+            mouse_id,
+            state,
+        } => {
+            conn.execute(
+                "INSERT INTO games (game_id, room_id, trapper_id, mouse_id, initial_state, started_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    game_id.to_string(),
+                    room_id,
+                    trapper_id.to_string(),
+                    mouse_id.to_string(),
+                    to_json(&state),
+                    now_ms()
+                ],
+            )?;
+        }
+        ArchiveEvent::ActionApplied {
+            game_id,
+            ply,
+            side,
+            action,
+            auto,
+        } => {
+            conn.execute(
+                "INSERT INTO actions (game_id, ply, side, action, auto, at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    game_id.to_string(),
+                    ply,
+                    to_json(&side),
+                    to_json(&action),
+                    auto,
+                    now_ms()
+                ],
+            )?;
+        }
+        ArchiveEvent::GameEnded { game_id, status } => {
+            conn.execute(
+                "UPDATE games SET ended_at = ?2, status = ?3, winner = ?4 WHERE game_id = ?1",
+                params![
+                    game_id.to_string(),
+                    now_ms(),
+                    to_json(&status),
+                    status.winner().map(|w| to_json(&w))
+                ],
+            )?;
+            update_ratings(conn, &game_id.to_string(), &status)?;
+        }
+        ArchiveEvent::PlayerSeen { player_id, name } => {
+            conn.execute(
+                "INSERT INTO players (player_id, name, created_at, last_seen)
+                 VALUES (?1, ?2, ?3, ?3)
+                 ON CONFLICT (player_id) DO UPDATE SET name = excluded.name, last_seen = excluded.last_seen",
+                params![player_id.to_string(), name, now_ms()],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn game_ids(conn: &Connection, limit: u32, offset: u32) -> rusqlite::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT game_id FROM games ORDER BY started_at DESC LIMIT ?1 OFFSET ?2")?;
+    let rows = stmt.query_map(params![limit, offset], |row| row.get(0))?;
+    rows.collect()
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(idx: usize, text: &str) -> rusqlite::Result<T> {
+    serde_json::from_str(text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn load_game(conn: &Connection, game_id: &str) -> rusqlite::Result<Option<GameRecord>> {
+    let game = conn
+        .query_row(
+            "SELECT g.game_id, g.room_id, r.name, g.trapper_id, g.mouse_id, g.initial_state,
+                    g.started_at, g.ended_at, g.status
+             FROM games g JOIN rooms r ON r.room_id = g.room_id
+             WHERE g.game_id = ?1",
+            params![game_id],
+            |row| {
+                let initial_state: String = row.get(5)?;
+                let status: Option<String> = row.get(8)?;
+                Ok(GameRecord {
+                    game_id: row.get(0)?,
+                    room_id: row.get(1)?,
+                    room_name: row.get(2)?,
+                    trapper_id: row.get(3)?,
+                    mouse_id: row.get(4)?,
+                    initial_state: from_json(5, &initial_state)?,
+                    started_at: row.get(6)?,
+                    ended_at: row.get(7)?,
+                    status: status.map(|s| from_json(8, &s)).transpose()?,
+                    actions: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+
+    let Some(mut game) = game else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT ply, side, action, auto, at FROM actions WHERE game_id = ?1 ORDER BY ply",
+    )?;
+    let rows = stmt.query_map(params![game_id], |row| {
+        let side: String = row.get(1)?;
+        let action: String = row.get(2)?;
+        Ok(ActionRecord {
+            ply: row.get(0)?,
+            side: from_json(1, &side)?,
+            action: from_json(2, &action)?,
+            auto: row.get(3)?,
+            at: row.get(4)?,
+        })
+    })?;
+    game.actions = rows.collect::<rusqlite::Result<_>>()?;
+
+    Ok(Some(game))
+}
+
+fn load_profile(conn: &Connection, player_id: &str) -> rusqlite::Result<Option<Profile>> {
+    let name: Option<String> = conn
+        .query_row(
+            "SELECT name FROM players WHERE player_id = ?1",
+            params![player_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let Some(name) = name else {
+        return Ok(None);
+    };
+
+    let (games_played, games_won) = conn.query_row(
+        "SELECT COUNT(*),
+                COALESCE(SUM((trapper_id = ?1 AND winner = ?2) OR (mouse_id = ?1 AND winner = ?3)), 0)
+         FROM games
+         WHERE (trapper_id = ?1 OR mouse_id = ?1) AND ended_at IS NOT NULL AND status != ?4",
+        params![
+            player_id,
+            to_json(&Turn::Trapper),
+            to_json(&Turn::Mouse),
+            to_json(&GameStatus::Aborted)
+        ],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let ratings = load_ratings(conn, player_id)?;
+
+    Ok(Some(Profile {
+        player_id: player_id.to_string(),
+        name,
+        games_played,
+        games_won,
+        trapper_rating: ratings.trapper.round() as i32,
+        mouse_rating: ratings.mouse.round() as i32,
+    }))
+}
+
+fn load_rating(conn: &Connection, player_id: &str, role: Turn) -> rusqlite::Result<f64> {
+    conn.query_row(
+        "SELECT rating FROM ratings WHERE player_id = ?1 AND role = ?2",
+        params![player_id, to_json(&role)],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(|r| r.unwrap_or(DEFAULT_RATING))
+}
+
+fn load_ratings(conn: &Connection, player_id: &str) -> rusqlite::Result<Ratings> {
+    Ok(Ratings {
+        trapper: load_rating(conn, player_id, Turn::Trapper)?,
+        mouse: load_rating(conn, player_id, Turn::Mouse)?,
+    })
+}
+
+fn is_registered(conn: &Connection, player_id: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM players WHERE player_id = ?1)",
+        params![player_id],
+        |row| row.get(0),
+    )
+}
+
+fn store_rating(
+    conn: &Connection,
+    player_id: &str,
+    role: Turn,
+    rating: f64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO ratings (player_id, role, rating, games) VALUES (?1, ?2, ?3, 1)
+         ON CONFLICT (player_id, role) DO UPDATE SET rating = excluded.rating, games = games + 1",
+        params![player_id, to_json(&role), rating],
+    )?;
+    Ok(())
+}
+
+fn update_ratings(conn: &Connection, game_id: &str, status: &GameStatus) -> rusqlite::Result<()> {
+    let score = match (status, status.winner()) {
+        (GameStatus::Draw, _) => 0.5,
+        (_, Some(Turn::Trapper)) => 1.0,
+        (_, Some(Turn::Mouse)) => 0.0,
+        (_, None) => return Ok(()),
+    };
+
+    let players: Option<(String, String)> = conn
+        .query_row(
+            "SELECT trapper_id, mouse_id FROM games WHERE game_id = ?1",
+            params![game_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let Some((trapper_id, mouse_id)) = players else {
+        return Ok(());
+    };
+
+    if trapper_id == mouse_id
+        || !is_registered(conn, &trapper_id)?
+        || !is_registered(conn, &mouse_id)?
+    {
+        return Ok(());
+    }
+
+    let trapper = load_rating(conn, &trapper_id, Turn::Trapper)?;
+    let mouse = load_rating(conn, &mouse_id, Turn::Mouse)?;
+    let delta = elo_delta(trapper, mouse, score);
+
+    store_rating(conn, &trapper_id, Turn::Trapper, trapper + delta)?;
+    store_rating(conn, &mouse_id, Turn::Mouse, mouse - delta)?;
+    Ok(())
+}
+
+fn load_leaderboard(
+    conn: &Connection,
+    role: Turn,
+    limit: u32,
+) -> rusqlite::Result<Vec<LeaderboardEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT r.player_id, p.name, r.rating, r.games
+         FROM ratings r JOIN players p ON p.player_id = r.player_id
+         WHERE r.role = ?1
+         ORDER BY r.rating DESC, r.games DESC
+         LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![to_json(&role), limit], |row| {
+        Ok(LeaderboardEntry {
+            rank: 0,
+            player_id: row.get(0)?,
+            name: row.get(1)?,
+            rating: row.get::<_, f64>(2)?.round() as i32,
+            games: row.get(3)?,
+        })
+    })?;
+
+    rows.enumerate()
+        .map(|(i, row)| {
+            row.map(|e| LeaderboardEntry {
+                rank: i as u32 + 1,
+                ..e
+            })
+        })
+        .collect()
+}
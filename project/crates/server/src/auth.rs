@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::player::{validate_name, Identity};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const TOKEN_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const ACCOUNT_NAMESPACE: Uuid = Uuid::from_u128(0x6f1c_2a4e_9b0d_4c77_a3e8_51d2_0c9f_7b16);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: Uuid,
+    name: String,
+    exp: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Session {
+    pub token: String,
+    pub player_id: String,
+    pub name: String,
+    pub expires_at: u64,
+}
+
+pub struct Auth {
+    secret: Vec<u8>,
+    accounts: HashMap<String, String>,
+}
+
+impl Auth {
+    pub fn load(path: &str, secret: Option<String>) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read accounts file {}: {}", path, e))?;
+
+        let mut accounts = HashMap::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((username, hash)) = line.split_once(':') else {
+                return Err(format!("{}:{}: expected `username:hash`", path, n + 1));
+            };
+            let username =
+                validate_name(username).map_err(|e| format!("{}:{}: {}", path, n + 1, e))?;
+            PasswordHash::new(hash).map_err(|e| format!("{}:{}: {}", path, n + 1, e))?;
+            accounts.insert(username, hash.to_string());
+        }
+
+        let secret = match secret {
+            Some(s) if !s.is_empty() => s.into_bytes(),
+            _ => {
+                tracing::warn!("TTM_AUTH_SECRET is not set, sessions will not survive a restart");
+                [Uuid::new_v4(), Uuid::new_v4()]
+                    .iter()
+                    .flat_map(|u| u.into_bytes())
+                    .collect()
+            }
+        };
+
+        Ok(Self { secret, accounts })
+    }
+
+    pub fn login(&self, username: &str, password: &str) -> Result<Session, String> {
+        let invalid = || "Invalid username or password".to_string();
+
+        let (name, hash) = self
+            .accounts
+            .get_key_value(username.trim())
+            .ok_or_else(invalid)?;
+        if !verify_password(password, hash) {
+            return Err(invalid());
+        }
+
+        let claims = Claims {
+            sub: account_id(name),
+            name: name.clone(),
+            exp: now_secs() + TOKEN_TTL.as_secs(),
+        };
+        let token = self.sign(&claims);
+
+        Ok(Session {
+            token,
+            player_id: claims.sub.to_string(),
+            name: claims.name,
+            expires_at: claims.exp,
+        })
+    }
+
+    pub fn player_id(&self, username: &str) -> Option<Uuid> {
+        self.accounts
+            .get_key_value(username.trim())
+            .map(|(name, _)| account_id(name))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Identity, String> {
+        let invalid = || "Invalid session token".to_string();
+
+        let (payload, sig) = token.split_once('.').ok_or_else(invalid)?;
+        let sig = URL_SAFE_NO_PAD.decode(sig).map_err(|_| invalid())?;
+        self.mac(payload.as_bytes())
+            .verify_slice(&sig)
+            .map_err(|_| invalid())?;
+
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        let claims: Claims = serde_json::from_slice(&payload).map_err(|_| invalid())?;
+        if claims.exp <= now_secs() {
+            return Err("Session token has expired".to_string());
+        }
+
+        Ok(Identity {
+            player_id: claims.sub,
+            name: claims.name,
+        })
+    }
+
+    fn sign(&self, claims: &Claims) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap_or_default());
+        let sig = self.mac(payload.as_bytes()).finalize().into_bytes();
+        format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(sig))
+    }
+
+    fn mac(&self, data: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key size");
+        mac.update(data);
+        mac
+    }
+}
+
+fn account_id(username: &str) -> Uuid {
+    Uuid::new_v5(&ACCOUNT_NAMESPACE, username.as_bytes())
+}
+
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::encode_b64(Uuid::new_v4().as_bytes()).map_err(|e| e.to_string())?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth(secret: &str) -> Auth {
+        Auth {
+            secret: secret.as_bytes().to_vec(),
+            accounts: HashMap::new(),
+        }
+    }
+
+    fn claims(name: &str, exp: u64) -> Claims {
+        Claims {
+            sub: account_id(name),
+            name: name.to_string(),
+            exp,
+        }
+    }
+
+    fn valid_until() -> u64 {
+        now_secs() + 60
+    }
+
+    #[test]
+    fn verify_accepts_a_signed_token() {
+        let auth = auth("secret");
+        let claims = claims("alice", valid_until());
+        let token = auth.sign(&claims);
+
+        let identity = auth.verify(&token).unwrap();
+        assert_eq!(identity.player_id, claims.sub);
+        assert_eq!(identity.name, "alice");
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_payload() {
+        let auth = auth("secret");
+        let token = auth.sign(&claims("alice", valid_until()));
+        let (_, sig) = token.split_once('.').unwrap();
+
+        let forged = serde_json::to_vec(&claims("mallory", valid_until())).unwrap();
+        let token = format!("{}.{}", URL_SAFE_NO_PAD.encode(forged), sig);
+        assert!(auth.verify(&token).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_signature() {
+        let auth = auth("secret");
+        let token = auth.sign(&claims("alice", valid_until()));
+        let (payload, sig) = token.split_once('.').unwrap();
+
+        let mut sig = URL_SAFE_NO_PAD.decode(sig).unwrap();
+        sig[0] ^= 1;
+        let token = format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(sig));
+        assert!(auth.verify(&token).is_err());
+        assert!(auth.verify(payload).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_an_expired_token() {
+        let auth = auth("secret");
+        let token = auth.sign(&claims("alice", now_secs() - 1));
+
+        assert_eq!(
+            auth.verify(&token).unwrap_err(),
+            "Session token has expired"
+        );
+    }
+
+    #[test]
+    fn verify_rejects_a_token_signed_with_another_secret() {
+        let token = auth("other").sign(&claims("alice", valid_until()));
+        assert!(auth("secret").verify(&token).is_err());
+    }
+
+    #[test]
+    fn login_issues_a_token_that_verifies() {
+        let mut auth = auth("secret");
+        auth.accounts
+            .insert("alice".to_string(), hash_password("hunter2").unwrap());
+
+        assert!(auth.login("alice", "wrong").is_err());
+        assert!(auth.login("bob", "hunter2").is_err());
+
+        let session = auth.login("alice", "hunter2").unwrap();
+        let identity = auth.verify(&session.token).unwrap();
+        assert_eq!(identity.player_id.to_string(), session.player_id);
+        assert_eq!(identity.name, "alice");
+    }
+}
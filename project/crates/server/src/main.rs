@@ -0,0 +1,157 @@
+mod app;
+mod archive;
+mod auth;
+mod metrics;
+mod net;
+mod player;
+mod rating;
+mod room;
+
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+use std::{error::Error, net::SocketAddr, time::Duration};
+use tracing_subscriber::EnvFilter;
+
+use crate::app::AppState;
+use crate::archive::Archive;
+use crate::auth::Auth;
+use crate::room::journal::Journal;
+
+const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(10);
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(3);
+const RETRY_AFTER_SECS: u64 = 15;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    if std::env::args().nth(1).as_deref() == Some("hash-password") {
+        let mut password = String::new();
+        std::io::stdin().read_line(&mut password)?;
+        println!(
+            "{}",
+            auth::hash_password(password.trim_end_matches(['\r', '\n']))?
+        );
+        return Ok(());
+    }
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let db_path = std::env::var("TTM_DB_PATH").unwrap_or_else(|_| "games.db".to_string());
+    let archive = Archive::open(&db_path)?;
+    tracing::info!("Archiving games to {}", db_path);
+
+    let (journal, saved_rooms) = Journal::open(&db_path)?;
+
+    let auth = match std::env::var("TTM_ACCOUNTS_FILE") {
+        Ok(path) => {
+            let auth = Auth::load(&path, std::env::var("TTM_AUTH_SECRET").ok())?;
+            tracing::info!("Authentication enabled, accounts from {}", path);
+            Some(auth)
+        }
+        Err(_) => None,
+    };
+
+    let admin_token = std::env::var("TTM_ADMIN_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty());
+    if admin_token.is_some() {
+        tracing::info!("Admin API enabled");
+    }
+
+    let state = AppState::new(archive, journal, auth, admin_token);
+    state.manager.restore_rooms(saved_rooms).await;
+
+    let app = Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route("/login", post(net::http::login))
+        .route("/ws", get(net::ws::ws_handler))
+        .route(
+            "/rooms",
+            get(net::http::list_rooms).post(net::http::create_room),
+        )
+        .route("/rooms/:room_id", get(net::http::get_room))
+        .route("/games", get(net::http::list_games))
+        .route("/games/:game_id", get(net::http::get_game))
+        .route("/players/:player_id", get(net::http::get_player))
+        .route("/leaderboard", get(net::http::leaderboard))
+        .route("/stats/queues", get(net::http::queue_stats))
+        .route("/metrics", get(net::http::metrics))
+        .route("/admin/rooms", get(net::admin::list_rooms))
+        .route("/admin/rooms/:room_id", delete(net::admin::close_room))
+        .route("/admin/clients", get(net::admin::list_clients))
+        .route("/admin/clients/:client_id", delete(net::admin::kick_client))
+        .route("/admin/announce", post(net::admin::announce))
+        .route(
+            "/admin/maintenance",
+            get(net::admin::get_maintenance).put(net::admin::set_maintenance),
+        )
+        .with_state(state.clone());
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
+    tracing::info!("Server listening on ws://{}/ws", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let signalled = state.clone();
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        let signal = shutdown_signal().await;
+        tracing::info!("Received {}", signal);
+        signalled
+            .manager
+            .begin_shutdown("Server is restarting".to_string(), RETRY_AFTER_SECS);
+    });
+
+    let drained = async {
+        server.await?;
+        state.wait_sockets_closed().await;
+        Ok::<_, std::io::Error>(())
+    };
+
+    let mut shutdown_rx = state.manager.subscribe_shutdown();
+    let deadline = async {
+        let _ = shutdown_rx.wait_for(Option::is_some).await;
+        tokio::time::sleep(SHUTDOWN_DEADLINE).await;
+    };
+
+    tokio::select! {
+        res = drained => res?,
+        _ = deadline => tracing::warn!("Shutdown deadline passed, dropping remaining connections"),
+    }
+
+    state.flush_storage(FLUSH_TIMEOUT).await;
+    tracing::info!("Server stopped");
+
+    Ok(())
+}
+
+async fn shutdown_signal() -> &'static str {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => "Ctrl-C",
+        _ = terminate => "SIGTERM",
+    }
+}
@@ -0,0 +1,161 @@
+use prometheus::core::Collector;
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, Result, TextEncoder,
+};
+
+use shared::rules::GameError;
+use shared::types::GameStatus;
+
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    pub connected_clients: IntGauge,
+    pub rooms: IntGaugeVec,
+    pub games_started: IntCounter,
+    pub games_finished: IntCounterVec,
+    pub actions_applied: IntCounter,
+    pub actions_rejected: IntCounterVec,
+    pub message_bytes: HistogramVec,
+    pub command_seconds: HistogramVec,
+    pub command_wait_seconds: HistogramVec,
+    pub room_crashes: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("ttm".to_string()), None).expect("metric prefix is valid");
+
+        let connected_clients = register(
+            &registry,
+            IntGauge::new("connected_clients", "Open WebSocket connections"),
+        );
+        let rooms = register(
+            &registry,
+            IntGaugeVec::new(Opts::new("rooms", "Active rooms by state"), &["state"]),
+        );
+        let games_started = register(
+            &registry,
+            IntCounter::new("games_started_total", "Games started"),
+        );
+        let games_finished = register(
+            &registry,
+            IntCounterVec::new(
+                Opts::new("games_finished_total", "Games finished by outcome"),
+                &["outcome"],
+            ),
+        );
+        let actions_applied = register(
+            &registry,
+            IntCounter::new("actions_applied_total", "Player and bot actions applied"),
+        );
+        let actions_rejected = register(
+            &registry,
+            IntCounterVec::new(
+                Opts::new("actions_rejected_total", "Actions rejected by reason"),
+                &["reason"],
+            ),
+        );
+        let message_bytes = register(
+            &registry,
+            HistogramVec::new(
+                HistogramOpts::new("message_bytes", "WebSocket text message sizes")
+                    .buckets(buckets(64.0, 4.0, 7)),
+                &["direction"],
+            ),
+        );
+        let command_seconds = register(
+            &registry,
+            HistogramVec::new(
+                HistogramOpts::new(
+                    "room_command_seconds",
+                    "Time a room actor spends on a command",
+                )
+                .buckets(buckets(0.00001, 4.0, 10)),
+                &["command"],
+            ),
+        );
+        let command_wait_seconds = register(
+            &registry,
+            HistogramVec::new(
+                HistogramOpts::new(
+                    "room_command_wait_seconds",
+                    "Time a command waits in a room's queue",
+                )
+                .buckets(buckets(0.0001, 4.0, 10)),
+                &["command"],
+            ),
+        );
+        let room_crashes = register(
+            &registry,
+            IntCounter::new("room_crashes_total", "Room tasks that panicked"),
+        );
+
+        Self {
+            registry,
+            connected_clients,
+            rooms,
+            games_started,
+            games_finished,
+            actions_applied,
+            actions_rejected,
+            message_bytes,
+            command_seconds,
+            command_wait_seconds,
+            room_crashes,
+        }
+    }
+
+    pub fn game_finished(&self, status: &GameStatus) {
+        let outcome = match status {
+            GameStatus::Running => return,
+            GameStatus::TrapperWon => "trapper_won",
+            GameStatus::MouseWon => "mouse_won",
+            GameStatus::Resigned { .. } => "resigned",
+            GameStatus::Draw => "draw",
+            GameStatus::Aborted => "aborted",
+            GameStatus::TimeOut { .. } => "timeout",
+            GameStatus::Abandoned { .. } => "abandoned",
+        };
+        self.games_finished.with_label_values(&[outcome]).inc();
+    }
+
+    pub fn action_rejected(&self, error: &GameError) {
+        let reason = match error {
+            GameError::GameEnded => "GameEnded",
+            GameError::WrongTurn => "WrongTurn",
+            GameError::OutsideBoard => "OutsideBoard",
+            GameError::Blocked => "Blocked",
+            GameError::BlockMouse => "BlockMouse",
+            GameError::NotNeighbor => "NotNeighbor",
+        };
+        self.actions_rejected.with_label_values(&[reason]).inc();
+    }
+
+    pub fn render(&self) -> String {
+        let mut buf = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+            tracing::warn!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+fn register<C: Collector + Clone + 'static>(registry: &Registry, metric: Result<C>) -> C {
+    let metric = metric.expect("metric definition is valid");
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric names are unique");
+    metric
+}
+
+fn buckets(start: f64, factor: f64, count: usize) -> Vec<f64> {
+    exponential_buckets(start, factor, count).expect("bucket parameters are valid")
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
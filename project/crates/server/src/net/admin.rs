@@ -0,0 +1,137 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use shared::net::ServerMsg;
+
+use crate::app::AppState;
+use crate::net::clients::ClientInfo;
+use crate::net::http::ApiError;
+use crate::room::manager::AdminRoom;
+
+const CLOSE_REASON: &str = "Closed by an administrator";
+const KICK_REASON: &str = "Disconnected by an administrator";
+
+#[derive(Debug, Deserialize)]
+pub struct AnnounceBody {
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Delivered {
+    pub delivered: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Maintenance {
+    pub enabled: bool,
+}
+
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let Some(expected) = state.admin_token.as_deref() else {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "Admin API is not enabled",
+        ));
+    };
+
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if Sha256::digest(given) != Sha256::digest(expected) {
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "Invalid admin token",
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_rooms(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AdminRoom>>, ApiError> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.manager.admin_rooms().await))
+}
+
+pub async fn close_room(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    authorize(&state, &headers)?;
+    state
+        .manager
+        .close_room(&room_id, CLOSE_REASON.to_string())
+        .await
+        .map_err(|e| ApiError::new(StatusCode::NOT_FOUND, e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_clients(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ClientInfo>>, ApiError> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.clients.list()))
+}
+
+pub async fn kick_client(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(client_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    authorize(&state, &headers)?;
+    if !state.clients.kick(client_id, KICK_REASON.to_string()) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "Client not found"));
+    }
+    tracing::info!("Client {} was kicked by an administrator", client_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn announce(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<AnnounceBody>,
+) -> Result<Json<Delivered>, ApiError> {
+    authorize(&state, &headers)?;
+
+    let message = body.message.trim().to_string();
+    if message.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "Announcement must not be empty",
+        ));
+    }
+
+    let delivered = state.clients.broadcast(ServerMsg::Announcement { message });
+    Ok(Json(Delivered { delivered }))
+}
+
+pub async fn get_maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Maintenance>, ApiError> {
+    authorize(&state, &headers)?;
+    Ok(Json(Maintenance {
+        enabled: state.manager.in_maintenance(),
+    }))
+}
+
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<Maintenance>,
+) -> Result<Json<Maintenance>, ApiError> {
+    authorize(&state, &headers)?;
+    state.manager.set_maintenance(body.enabled);
+    Ok(Json(body))
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use shared::net::ServerMsg;
+
+use crate::net::outbox::Outbox;
+use crate::player::Identity;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientInfo {
+    pub client_id: Uuid,
+    pub player_id: Uuid,
+    pub name: String,
+    pub ip: IpAddr,
+    pub connected_secs: u64,
+}
+
+struct Entry {
+    identity: Identity,
+    ip: IpAddr,
+    since: Instant,
+    tx: Outbox,
+    kick: Arc<Notify>,
+}
+
+#[derive(Clone, Default)]
+pub struct Clients {
+    inner: Arc<Mutex<HashMap<Uuid, Entry>>>,
+}
+
+impl Clients {
+    pub fn register(
+        &self,
+        client_id: Uuid,
+        identity: &Identity,
+        ip: IpAddr,
+        tx: Outbox,
+    ) -> Arc<Notify> {
+        let kick = Arc::new(Notify::new());
+        self.lock().insert(
+            client_id,
+            Entry {
+                identity: identity.clone(),
+                ip,
+                since: Instant::now(),
+                tx,
+                kick: kick.clone(),
+            },
+        );
+        kick
+    }
+
+    pub fn update(&self, client_id: Uuid, identity: &Identity) {
+        if let Some(e) = self.lock().get_mut(&client_id) {
+            e.identity = identity.clone();
+        }
+    }
+
+    pub fn unregister(&self, client_id: Uuid) {
+        self.lock().remove(&client_id);
+    }
+
+    pub fn list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<ClientInfo> = self
+            .lock()
+            .iter()
+            .map(|(id, e)| ClientInfo {
+                client_id: *id,
+                player_id: e.identity.player_id,
+                name: e.identity.name.clone(),
+                ip: e.ip,
+                connected_secs: e.since.elapsed().as_secs(),
+            })
+            .collect();
+        clients.sort_by_key(|c| std::cmp::Reverse(c.connected_secs));
+        clients
+    }
+
+    pub fn kick(&self, client_id: Uuid, reason: String) -> bool {
+        let clients = self.lock();
+        let Some(e) = clients.get(&client_id) else {
+            return false;
+        };
+        let _ = e.tx.send(ServerMsg::Disconnected { reason });
+        e.kick.notify_one();
+        true
+    }
+
+    pub fn broadcast(&self, msg: ServerMsg) -> usize {
+        let clients = self.lock();
+        clients
+            .values()
+            .filter(|e| e.tx.send(msg.clone()).is_ok())
+            .count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, Entry>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,263 @@
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use shared::net::{LeaderboardEntry, Profile, RoomInfo};
+use shared::rules::DEFAULT_RADIUS;
+use shared::types::{AbandonPolicy, TimeControl, TimeoutPolicy, Turn};
+
+use crate::app::AppState;
+use crate::archive::GameRecord;
+use crate::auth::Session;
+use crate::room::actor::{validate_room_name, RoomConfig};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoomBody {
+    pub name: String,
+    #[serde(default)]
+    pub vs_bot: bool,
+    pub radius: Option<i32>,
+    pub time_control: Option<TimeControl>,
+    #[serde(default)]
+    pub on_timeout: TimeoutPolicy,
+    #[serde(default)]
+    pub on_abandon: AbandonPolicy,
+    #[serde(default)]
+    pub private: bool,
+    pub password: Option<String>,
+    pub invited: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginBody {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GamesQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    pub role: Option<Turn>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    message: String,
+}
+
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(ErrorBody {
+                message: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+pub async fn login(
+    State(state): State<AppState>,
+    Json(body): Json<LoginBody>,
+) -> Result<Json<Session>, ApiError> {
+    let Some(auth) = state.auth.clone() else {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "Authentication is not enabled",
+        ));
+    };
+
+    tokio::task::spawn_blocking(move || auth.login(&body.username, &body.password))
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(|e| ApiError::new(StatusCode::UNAUTHORIZED, e))
+}
+
+pub async fn list_rooms(State(state): State<AppState>) -> Json<Vec<RoomInfo>> {
+    Json(state.manager.list_rooms().await)
+}
+
+pub async fn get_room(
+    State(state): State<AppState>,
+    Path(room_id): Path<String>,
+) -> Result<Json<RoomInfo>, ApiError> {
+    state
+        .manager
+        .get_room(&room_id)
+        .await
+        .map(Json)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Room not found"))
+}
+
+pub async fn create_room(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(body): Json<CreateRoomBody>,
+) -> Result<(StatusCode, Json<RoomInfo>), ApiError> {
+    if state.manager.is_shutting_down() {
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is shutting down",
+        ));
+    }
+    if state.manager.in_maintenance() {
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Server is in maintenance mode, new rooms are disabled",
+        ));
+    }
+
+    let name =
+        validate_room_name(&body.name).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    let invited_player = state
+        .resolve_invited(body.invited.as_deref())
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    if !state.room_limiter.try_create(addr.ip()) {
+        return Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many rooms created, try again later",
+        ));
+    }
+
+    let config = RoomConfig {
+        vs_bot: body.vs_bot,
+        radius: body.radius.unwrap_or(DEFAULT_RADIUS),
+        time_control: body.time_control,
+        on_timeout: body.on_timeout,
+        on_abandon: body.on_abandon,
+        private: body.private,
+        password: body.password,
+        password_hash: None,
+        invited_player,
+    };
+    let info = state
+        .manager
+        .create_room(name, config)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+pub async fn list_games(
+    State(state): State<AppState>,
+    Query(query): Query<GamesQuery>,
+) -> Result<Json<Vec<GameRecord>>, ApiError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0);
+
+    state
+        .archive
+        .list_games(limit, offset)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+pub async fn get_game(
+    State(state): State<AppState>,
+    Path(game_id): Path<String>,
+) -> Result<Json<GameRecord>, ApiError> {
+    state
+        .archive
+        .get_game(game_id)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map(Json)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Game not found"))
+}
+
+pub async fn get_player(
+    State(state): State<AppState>,
+    Path(player_id): Path<String>,
+) -> Result<Json<Profile>, ApiError> {
+    state
+        .archive
+        .get_profile(player_id)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .map(Json)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Player not found"))
+}
+
+pub async fn leaderboard(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<Vec<LeaderboardEntry>>, ApiError> {
+    let role = query.role.unwrap_or(Turn::Trapper);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    state
+        .archive
+        .leaderboard(role, limit)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueReport {
+    pub connections: usize,
+    pub outbound_queued: usize,
+    pub dropped_updates: u64,
+    pub slow_client_disconnects: u64,
+    pub room_commands_queued: usize,
+    pub room_commands_max: usize,
+}
+
+pub async fn queue_stats(State(state): State<AppState>) -> Json<QueueReport> {
+    let depths = state.manager.queue_depths().await;
+    let stats = &state.queue_stats;
+
+    Json(QueueReport {
+        connections: *state.sockets.borrow(),
+        outbound_queued: stats.queued.load(Ordering::Relaxed),
+        dropped_updates: stats.dropped_updates.load(Ordering::Relaxed),
+        slow_client_disconnects: stats.overflows.load(Ordering::Relaxed),
+        room_commands_queued: depths.iter().sum(),
+        room_commands_max: depths.into_iter().max().unwrap_or(0),
+    })
+}
+
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let metrics = &state.metrics;
+    metrics
+        .connected_clients
+        .set(*state.sockets.borrow() as i64);
+    for (room_state, count) in state.manager.room_states().await {
+        metrics.rooms.with_label_values(&[room_state]).set(count);
+    }
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(),
+    )
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+pub const MAX_MESSAGE_BYTES: usize = 8 * 1024;
+pub const MAX_FRAME_BYTES: usize = 64 * 1024;
+pub const MESSAGES_PER_SEC: f64 = 20.0;
+pub const MESSAGE_BURST: f64 = 40.0;
+pub const ROOMS_PER_MINUTE: f64 = 6.0;
+pub const ROOM_BURST: f64 = 5.0;
+pub const MAX_STRIKES: f64 = 10.0;
+pub const STRIKE_DECAY: Duration = Duration::from_secs(10);
+const MAX_TRACKED_IPS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    per_sec: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: f64, per_sec: f64) -> Self {
+        Self {
+            capacity,
+            per_sec,
+            tokens: capacity,
+            last: Instant::now(),
+        }
+    }
+
+    pub fn try_take(&mut self) -> bool {
+        self.refill();
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.last = now;
+    }
+
+    fn is_full(&mut self) -> bool {
+        self.refill();
+        self.tokens >= self.capacity
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RoomLimiter {
+    buckets: Arc<Mutex<HashMap<IpAddr, TokenBucket>>>,
+}
+
+impl RoomLimiter {
+    pub fn try_create(&self, ip: IpAddr) -> bool {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_TRACKED_IPS {
+            buckets.retain(|_, b| !b.is_full());
+        }
+        buckets
+            .entry(ip)
+            .or_insert_with(|| TokenBucket::new(ROOM_BURST, ROOMS_PER_MINUTE / 60.0))
+            .try_take()
+    }
+}
@@ -0,0 +1,6 @@
+pub mod admin;
+pub mod clients;
+pub mod http;
+pub mod limits;
+pub mod outbox;
+pub mod ws;
@@ -0,0 +1,245 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
+use tokio::sync::{mpsc, Notify};
+
+use shared::net::ServerMsg;
+
+pub const OUTBOX_CAPACITY: usize = 256;
+
+#[derive(Debug, Default)]
+pub struct QueueStats {
+    pub queued: AtomicUsize,
+    pub dropped_updates: AtomicU64,
+    pub overflows: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct Closed;
+
+#[derive(Clone)]
+pub struct Outbox {
+    tx: mpsc::Sender<ServerMsg>,
+    latest: Arc<Mutex<Option<ServerMsg>>>,
+    overflow: Arc<Notify>,
+    stats: Arc<QueueStats>,
+}
+
+pub struct OutboxRx {
+    rx: mpsc::Receiver<ServerMsg>,
+    latest: Arc<Mutex<Option<ServerMsg>>>,
+    stats: Arc<QueueStats>,
+}
+
+pub fn outbox(stats: Arc<QueueStats>) -> (Outbox, OutboxRx) {
+    let (tx, rx) = mpsc::channel(OUTBOX_CAPACITY);
+    let latest = Arc::new(Mutex::new(None));
+    let tx = Outbox {
+        tx,
+        latest: latest.clone(),
+        overflow: Arc::new(Notify::new()),
+        stats: stats.clone(),
+    };
+    (tx, OutboxRx { rx, latest, stats })
+}
+
+impl Outbox {
+    pub fn detached() -> Self {
+        outbox(Arc::default()).0
+    }
+
+    pub fn send(&self, msg: ServerMsg) -> Result<(), Closed> {
+        let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(pending) = latest.take() {
+            match self.tx.try_send(pending) {
+                Ok(()) => {
+                    self.stats.queued.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(TrySendError::Full(pending)) => {
+                    if matches!(msg, ServerMsg::GameUpdate { .. }) {
+                        self.stats.dropped_updates.fetch_add(1, Ordering::Relaxed);
+                        *latest = Some(msg);
+                    } else {
+                        *latest = Some(pending);
+                        self.overflowed();
+                    }
+                    return Ok(());
+                }
+                Err(TrySendError::Closed(_)) => return Err(Closed),
+            }
+        }
+
+        match self.tx.try_send(msg) {
+            Ok(()) => {
+                self.stats.queued.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(TrySendError::Full(msg @ ServerMsg::GameUpdate { .. })) => {
+                *latest = Some(msg);
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                self.overflowed();
+                Ok(())
+            }
+            Err(TrySendError::Closed(_)) => Err(Closed),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    pub async fn wait_overflow(&self) {
+        self.overflow.notified().await
+    }
+
+    fn overflowed(&self) {
+        self.stats.overflows.fetch_add(1, Ordering::Relaxed);
+        self.overflow.notify_one();
+    }
+}
+
+impl OutboxRx {
+    pub async fn recv(&mut self) -> Option<ServerMsg> {
+        {
+            let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+            match self.rx.try_recv() {
+                Ok(msg) => {
+                    self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+                    return Some(msg);
+                }
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => {
+                    if let Some(msg) = latest.take() {
+                        return Some(msg);
+                    }
+                }
+            }
+        }
+
+        let msg = self.rx.recv().await?;
+        self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+        Some(msg)
+    }
+}
+
+impl Drop for OutboxRx {
+    fn drop(&mut self) {
+        self.stats
+            .queued
+            .fetch_sub(self.rx.len(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use shared::types::{BoardConfig, Coord, GameState, GameStatus, Turn};
+
+    use super::*;
+
+    fn update(n: i32) -> ServerMsg {
+        ServerMsg::GameUpdate {
+            state: GameState {
+                cfg: BoardConfig {
+                    radius: 6,
+                    initial_blocks: 0,
+                    seed: 0,
+                },
+                mouse: Coord { q: n, r: 0 },
+                blocks: Default::default(),
+                turn: Turn::Trapper,
+                status: GameStatus::Running,
+            },
+            clock: None,
+        }
+    }
+
+    fn update_no(msg: &ServerMsg) -> Option<i32> {
+        match msg {
+            ServerMsg::GameUpdate { state, .. } => Some(state.mouse.q),
+            _ => None,
+        }
+    }
+
+    fn fill(tx: &Outbox) {
+        for _ in 0..OUTBOX_CAPACITY {
+            tx.send(ServerMsg::OpponentReturned).unwrap();
+        }
+    }
+
+    async fn drain(rx: &mut OutboxRx) -> Vec<ServerMsg> {
+        let mut msgs = Vec::new();
+        while let Ok(Some(msg)) = tokio::time::timeout(Duration::from_millis(10), rx.recv()).await {
+            msgs.push(msg);
+        }
+        msgs
+    }
+
+    #[tokio::test]
+    async fn full_queue_keeps_only_the_latest_update() {
+        let stats = Arc::new(QueueStats::default());
+        let (tx, mut rx) = outbox(stats.clone());
+        fill(&tx);
+
+        tx.send(update(1)).unwrap();
+        tx.send(update(2)).unwrap();
+        tx.send(update(3)).unwrap();
+        assert_eq!(stats.dropped_updates.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.overflows.load(Ordering::Relaxed), 0);
+
+        let msgs = drain(&mut rx).await;
+        assert_eq!(msgs.len(), OUTBOX_CAPACITY + 1);
+        assert_eq!(update_no(&msgs[OUTBOX_CAPACITY]), Some(3));
+        assert_eq!(stats.queued.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn pending_update_is_sent_before_the_next_message() {
+        let (tx, mut rx) = outbox(Arc::default());
+        fill(&tx);
+        tx.send(update(1)).unwrap();
+
+        assert!(rx.recv().await.is_some());
+        assert!(rx.recv().await.is_some());
+        tx.send(ServerMsg::DrawOffered).unwrap();
+
+        let msgs = drain(&mut rx).await;
+        let n = msgs.len();
+        assert_eq!(n, OUTBOX_CAPACITY);
+        assert_eq!(update_no(&msgs[n - 2]), Some(1));
+        assert!(matches!(msgs[n - 1], ServerMsg::DrawOffered));
+    }
+
+    #[tokio::test]
+    async fn other_messages_overflow_a_full_queue() {
+        let stats = Arc::new(QueueStats::default());
+        let (tx, _rx) = outbox(stats.clone());
+        fill(&tx);
+
+        assert!(tx.send(ServerMsg::DrawOffered).is_ok());
+        assert_eq!(stats.overflows.load(Ordering::Relaxed), 1);
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), tx.wait_overflow())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn send_fails_once_the_receiver_is_gone() {
+        let stats = Arc::new(QueueStats::default());
+        let (tx, rx) = outbox(stats.clone());
+        tx.send(ServerMsg::OpponentReturned).unwrap();
+        assert_eq!(stats.queued.load(Ordering::Relaxed), 1);
+
+        drop(rx);
+        assert!(tx.is_closed());
+        assert!(tx.send(ServerMsg::OpponentReturned).is_err());
+        assert_eq!(stats.queued.load(Ordering::Relaxed), 0);
+        assert!(Outbox::detached().is_closed());
+    }
+}
@@ -0,0 +1,820 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+    time::{interval_at, sleep_until, Instant},
+};
+use uuid::Uuid;
+
+use shared::net::{ClientMsg, Limit, ServerMsg};
+use shared::rules::{DEFAULT_RADIUS, MAX_RADIUS, MIN_RADIUS};
+
+use crate::app::AppState;
+use crate::net::http::ApiError;
+use crate::net::limits::{
+    TokenBucket, MAX_FRAME_BYTES, MAX_MESSAGE_BYTES, MAX_STRIKES, MESSAGES_PER_SEC, MESSAGE_BURST,
+    STRIKE_DECAY,
+};
+use crate::net::outbox::{outbox, Outbox};
+use crate::player::{self, validate_name, Identity};
+use crate::room::actor::{validate_room_name, RoomCmd, RoomConfig, WRONG_PASSWORD};
+use crate::room::manager::Joiner;
+use crate::room::matchmaker::Ticket;
+
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+const IDLE_TIMEOUT: Duration = Duration::from_secs(45);
+const CLOSE_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+pub struct WsParams {
+    pub token: Option<String>,
+}
+
+pub async fn ws_handler(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<WsParams>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let identity = match state.auth.as_deref() {
+        None => None,
+        Some(auth) => {
+            let bearer = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .map(str::to_string);
+            let Some(token) = params.token.or(bearer) else {
+                return ApiError::new(StatusCode::UNAUTHORIZED, "Missing session token")
+                    .into_response();
+            };
+            match auth.verify(&token) {
+                Ok(identity) => Some(identity),
+                Err(e) => return ApiError::new(StatusCode::UNAUTHORIZED, e).into_response(),
+            }
+        }
+    };
+
+    ws.max_message_size(MAX_FRAME_BYTES)
+        .max_frame_size(MAX_FRAME_BYTES)
+        .on_upgrade(move |socket| handle_socket(state, socket, addr.ip(), identity))
+}
+
+async fn handle_socket(state: AppState, socket: WebSocket, ip: IpAddr, session: Option<Identity>) {
+    let client_id = Uuid::new_v4();
+    state.sockets.send_modify(|n| *n += 1);
+
+    let (out_tx, mut out_rx) = outbox(state.queue_stats.clone());
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let epoch = Instant::now();
+    let metrics = state.metrics.clone();
+
+    let mut sender = tokio::spawn(async move {
+        let mut ping = interval_at(epoch + PING_INTERVAL, PING_INTERVAL);
+        loop {
+            let frame = tokio::select! {
+                msg = out_rx.recv() => {
+                    let Some(msg) = msg else {
+                        break;
+                    };
+                    let Ok(text) = serde_json::to_string(&msg) else {
+                        continue;
+                    };
+                    metrics
+                        .message_bytes
+                        .with_label_values(&["out"])
+                        .observe(text.len() as f64);
+                    Message::Text(text)
+                }
+                _ = ping.tick() => {
+                    let sent = epoch.elapsed().as_micros() as u64;
+                    Message::Ping(sent.to_be_bytes().to_vec())
+                }
+            };
+            if ws_tx.send(frame).await.is_err() {
+                break;
+            }
+        }
+        let _ = ws_tx.send(Message::Close(None)).await;
+    });
+
+    {
+        let rooms = state.manager.list_rooms().await;
+        let _ = out_tx.send(ServerMsg::RoomList {
+            rooms,
+            next_cursor: None,
+        });
+    }
+
+    let authenticated = session.is_some();
+    let mut identity = session.unwrap_or_else(|| Identity::guest(client_id));
+    if authenticated {
+        let profile = player::register(&state.archive, &identity).await;
+        let _ = out_tx.send(ServerMsg::Profile { profile });
+    }
+    let kicked = state
+        .clients
+        .register(client_id, &identity, ip, out_tx.clone());
+
+    let mut current_room: Option<String> = None;
+    let mut queued = false;
+    let mut lobby_task: Option<JoinHandle<()>> = None;
+
+    let mut shutdown_rx = state.manager.subscribe_shutdown();
+    let (matched_tx, mut matched_rx) = mpsc::unbounded_channel::<String>();
+    let mut last_seen = Instant::now();
+    let mut messages = TokenBucket::new(MESSAGE_BURST, MESSAGES_PER_SEC);
+    let mut strikes = TokenBucket::new(MAX_STRIKES, 1.0 / STRIKE_DECAY.as_secs_f64());
+
+    loop {
+        let next = tokio::select! {
+            next = ws_rx.next() => next,
+            _ = kicked.notified() => break,
+            _ = out_tx.wait_overflow() => {
+                tracing::warn!("Closing connection {}: outgoing queue is full", client_id);
+                break;
+            }
+            _ = sleep_until(last_seen + IDLE_TIMEOUT) => {
+                tracing::info!("Closing idle connection {}", client_id);
+                break;
+            }
+            Some(notice) = async {
+                shutdown_rx.wait_for(Option::is_some).await.ok().and_then(|n| n.clone())
+            } => {
+                let _ = out_tx.send(notice);
+                break;
+            }
+            Some(room_id) = matched_rx.recv() => {
+                if !queued {
+                    let _ = state.manager.leave_room(&room_id, client_id).await;
+                    continue;
+                }
+                queued = false;
+                if let Some(r) = current_room.take().filter(|r| *r != room_id) {
+                    let _ = state.manager.leave_room(&r, client_id).await;
+                }
+                current_room = Some(room_id);
+                continue;
+            }
+        };
+        let Some(Ok(msg)) = next else {
+            break;
+        };
+
+        if matches!(msg, Message::Text(_) | Message::Binary(_)) && !messages.try_take() {
+            let limit = Limit::MessageRate;
+            let message = "Too many messages, slow down".to_string();
+            if reject(&out_tx, &mut strikes, client_id, limit, message) {
+                break;
+            }
+            continue;
+        }
+        last_seen = Instant::now();
+
+        match msg {
+            Message::Text(text) => {
+                state
+                    .metrics
+                    .message_bytes
+                    .with_label_values(&["in"])
+                    .observe(text.len() as f64);
+
+                if text.len() > MAX_MESSAGE_BYTES {
+                    let limit = Limit::MessageSize;
+                    let message = format!("Messages must be at most {} bytes", MAX_MESSAGE_BYTES);
+                    if reject(&out_tx, &mut strikes, client_id, limit, message) {
+                        break;
+                    }
+                    continue;
+                }
+
+                let parsed = serde_json::from_str::<ClientMsg>(&text);
+                let Ok(cmd) = parsed else {
+                    let _ = out_tx.send(ServerMsg::Error {
+                        message: "Invalid JSON or message shape".to_string(),
+                    });
+                    continue;
+                };
+
+                match cmd {
+                    ClientMsg::Login { name, player_id } => {
+                        if authenticated {
+                            let _ = out_tx.send(ServerMsg::Error {
+                                message: "Already authenticated with a session token".to_string(),
+                            });
+                            continue;
+                        }
+
+                        if current_room.is_some() {
+                            let _ = out_tx.send(ServerMsg::Error {
+                                message: "Leave the room before logging in".to_string(),
+                            });
+                            continue;
+                        }
+
+                        let name = match validate_name(&name) {
+                            Ok(name) => name,
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                                continue;
+                            }
+                        };
+                        let player_id = match player_id.as_deref().map(Uuid::parse_str) {
+                            None => Uuid::new_v4(),
+                            Some(Ok(id)) => id,
+                            Some(Err(_)) => {
+                                let _ = out_tx.send(ServerMsg::Error {
+                                    message: "Invalid player id".to_string(),
+                                });
+                                continue;
+                            }
+                        };
+
+                        identity = Identity { player_id, name };
+                        state.clients.update(client_id, &identity);
+                        let profile = player::register(&state.archive, &identity).await;
+                        let _ = out_tx.send(ServerMsg::Profile { profile });
+                    }
+
+                    ClientMsg::SetNickname { name } => {
+                        if authenticated {
+                            let _ = out_tx.send(ServerMsg::Error {
+                                message: "Authenticated players use their account name".to_string(),
+                            });
+                            continue;
+                        }
+
+                        if current_room.is_some() {
+                            let _ = out_tx.send(ServerMsg::Error {
+                                message: "Leave the room before changing nickname".to_string(),
+                            });
+                            continue;
+                        }
+
+                        match validate_name(&name) {
+                            Ok(name) => {
+                                identity.name = name;
+                                state.clients.update(client_id, &identity);
+                            }
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                                continue;
+                            }
+                        }
+
+                        let profile = player::register(&state.archive, &identity).await;
+                        let _ = out_tx.send(ServerMsg::Profile { profile });
+                    }
+
+                    ClientMsg::GetProfile { player_id } => {
+                        match state.archive.get_profile(player_id).await {
+                            Ok(Some(profile)) => {
+                                let _ = out_tx.send(ServerMsg::Profile { profile });
+                            }
+                            Ok(None) => {
+                                let _ = out_tx.send(ServerMsg::Error {
+                                    message: "Player not found".to_string(),
+                                });
+                            }
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                            }
+                        }
+                    }
+
+                    ClientMsg::GetLeaderboard { role, limit } => {
+                        let limit = limit.unwrap_or(20).clamp(1, 100);
+                        match state.archive.leaderboard(role, limit).await {
+                            Ok(entries) => {
+                                let _ = out_tx.send(ServerMsg::Leaderboard { role, entries });
+                            }
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                            }
+                        }
+                    }
+
+                    ClientMsg::ListRooms { query } => {
+                        match state.manager.query_rooms(&query).await {
+                            Ok((rooms, next_cursor)) => {
+                                let _ = out_tx.send(ServerMsg::RoomList { rooms, next_cursor });
+                            }
+                            Err(message) => {
+                                let _ = out_tx.send(ServerMsg::Error { message });
+                            }
+                        }
+                    }
+
+                    ClientMsg::RequestRematch { swap_roles } => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::RequestRematch {
+                                client_id,
+                                swap_roles,
+                            },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::AcceptRematch => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::AcceptRematch { client_id },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::SubscribeLobby => {
+                        if lobby_task.is_some() {
+                            continue;
+                        }
+
+                        let lobby_rx = state.manager.subscribe_lobby();
+                        let rooms = state.manager.list_rooms().await;
+                        let _ = out_tx.send(ServerMsg::RoomList {
+                            rooms,
+                            next_cursor: None,
+                        });
+
+                        lobby_task = Some(tokio::spawn(forward_lobby(
+                            state.clone(),
+                            lobby_rx,
+                            out_tx.clone(),
+                        )));
+                    }
+
+                    ClientMsg::UnsubscribeLobby => {
+                        if let Some(task) = lobby_task.take() {
+                            task.abort();
+                        }
+                    }
+
+                    ClientMsg::QuickPlay {
+                        preferred_role,
+                        board_size,
+                    } => {
+                        let radius = board_size.unwrap_or(DEFAULT_RADIUS);
+                        if !(MIN_RADIUS..=MAX_RADIUS).contains(&radius) {
+                            let _ = out_tx.send(ServerMsg::Error {
+                                message: format!(
+                                    "Board radius must be between {} and {}",
+                                    MIN_RADIUS, MAX_RADIUS
+                                ),
+                            });
+                            continue;
+                        }
+
+                        if let Some(r) = current_room.take() {
+                            let _ = state.manager.leave_room(&r, client_id).await;
+                        }
+
+                        let ticket = Ticket {
+                            client_id,
+                            identity: identity.clone(),
+                            preferred_role,
+                            radius,
+                            client_tx: out_tx.clone(),
+                            matched_tx: matched_tx.clone(),
+                        };
+                        match state.matchmaker.enqueue(ticket).await {
+                            Ok(()) => queued = true,
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                            }
+                        }
+                    }
+
+                    ClientMsg::CancelQuickPlay => {
+                        queued = false;
+                        state.matchmaker.cancel(client_id);
+                    }
+
+                    ClientMsg::AcceptBotGame => {
+                        if let Err(e) = state.matchmaker.accept_bot(client_id, out_tx.clone()) {
+                            let _ = out_tx.send(ServerMsg::Error { message: e });
+                        }
+                    }
+
+                    ClientMsg::CreateRoom {
+                        name,
+                        vs_bot,
+                        radius,
+                        role,
+                        time_control,
+                        on_timeout,
+                        on_abandon,
+                        private,
+                        password,
+                        invited,
+                    } => {
+                        let name = match validate_room_name(&name) {
+                            Ok(name) => name,
+                            Err(message) => {
+                                let limit = Limit::RoomName;
+                                if reject(&out_tx, &mut strikes, client_id, limit, message) {
+                                    break;
+                                }
+                                continue;
+                            }
+                        };
+                        let invited_player = match state.resolve_invited(invited.as_deref()) {
+                            Ok(id) => id,
+                            Err(message) => {
+                                let _ = out_tx.send(ServerMsg::Error { message });
+                                continue;
+                            }
+                        };
+                        if !state.room_limiter.try_create(ip) {
+                            let limit = Limit::RoomCreation;
+                            let message = "Too many rooms created, try again later".to_string();
+                            if reject(&out_tx, &mut strikes, client_id, limit, message) {
+                                break;
+                            }
+                            continue;
+                        }
+
+                        queued = false;
+                        state.matchmaker.cancel(client_id);
+                        if let Some(r) = current_room.take() {
+                            let _ = state.manager.leave_room(&r, client_id).await;
+                        }
+
+                        let config = RoomConfig {
+                            vs_bot,
+                            radius: radius.unwrap_or(DEFAULT_RADIUS),
+                            time_control,
+                            on_timeout,
+                            on_abandon,
+                            private,
+                            password: password.clone(),
+                            password_hash: None,
+                            invited_player,
+                        };
+                        let info = match state.manager.create_room(name, config).await {
+                            Ok(info) => info,
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                                continue;
+                            }
+                        };
+                        let room_id = info.room_id.clone();
+                        let secret = info.invite_code.clone().or(password);
+                        let _ = out_tx.send(ServerMsg::RoomCreated { room: info });
+
+                        match state
+                            .manager
+                            .join_room(
+                                &room_id,
+                                Joiner {
+                                    client_id,
+                                    identity: identity.clone(),
+                                    client_tx: out_tx.clone(),
+                                },
+                                role,
+                                secret.as_deref(),
+                            )
+                            .await
+                        {
+                            Ok(()) => current_room = Some(room_id),
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                            }
+                        }
+                    }
+
+                    ClientMsg::JoinRoom {
+                        room_id,
+                        role,
+                        password,
+                    } => {
+                        queued = false;
+                        state.matchmaker.cancel(client_id);
+                        if let Some(r) = current_room.take() {
+                            let _ = state.manager.leave_room(&r, client_id).await;
+                        }
+
+                        match state
+                            .manager
+                            .join_room(
+                                &room_id,
+                                Joiner {
+                                    client_id,
+                                    identity: identity.clone(),
+                                    client_tx: out_tx.clone(),
+                                },
+                                role,
+                                password.as_deref(),
+                            )
+                            .await
+                        {
+                            Ok(()) => current_room = Some(room_id),
+                            Err(e) => {
+                                let wrong_password = e == WRONG_PASSWORD;
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                                if wrong_password && strike(&mut strikes, client_id) {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    ClientMsg::JoinByCode { code, role } => {
+                        let Some(room_id) = state.manager.find_by_code(&code).await else {
+                            let _ = out_tx.send(ServerMsg::Error {
+                                message: "Unknown invite code".to_string(),
+                            });
+                            continue;
+                        };
+
+                        queued = false;
+                        state.matchmaker.cancel(client_id);
+                        if let Some(r) = current_room.take() {
+                            let _ = state.manager.leave_room(&r, client_id).await;
+                        }
+
+                        match state
+                            .manager
+                            .join_room(
+                                &room_id,
+                                Joiner {
+                                    client_id,
+                                    identity: identity.clone(),
+                                    client_tx: out_tx.clone(),
+                                },
+                                role,
+                                Some(&code),
+                            )
+                            .await
+                        {
+                            Ok(()) => current_room = Some(room_id),
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                            }
+                        }
+                    }
+
+                    ClientMsg::RejoinRoom { room_id, token } => {
+                        queued = false;
+                        state.matchmaker.cancel(client_id);
+                        if let Some(r) = current_room.take() {
+                            let _ = state.manager.leave_room(&r, client_id).await;
+                        }
+
+                        match state
+                            .manager
+                            .rejoin_room(
+                                &room_id,
+                                client_id,
+                                identity.clone(),
+                                token,
+                                out_tx.clone(),
+                            )
+                            .await
+                        {
+                            Ok(()) => current_room = Some(room_id),
+                            Err(e) => {
+                                let _ = out_tx.send(ServerMsg::Error { message: e });
+                            }
+                        }
+                    }
+
+                    ClientMsg::LeaveRoom => {
+                        if let Some(r) = current_room.take() {
+                            let _ = state.manager.leave_room(&r, client_id).await;
+                        }
+                    }
+
+                    ClientMsg::PlayerAction { action } => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::Action { client_id, action },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::Resign => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::Resign { client_id },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::OfferDraw => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::OfferDraw { client_id },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::AcceptDraw => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::AcceptDraw { client_id },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::AbortGame => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::Abort { client_id },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::Ready => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::Ready { client_id },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::UpdateRoom {
+                        radius,
+                        time_control,
+                        on_timeout,
+                        on_abandon,
+                    } => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::UpdateSettings {
+                                client_id,
+                                radius,
+                                time_control,
+                                on_timeout,
+                                on_abandon,
+                            },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::SwapRoles => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::SwapRoles { client_id },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::Kick { seat } => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::Kick { client_id, seat },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::Chat { text } => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::Chat { client_id, text },
+                        )
+                        .await;
+                    }
+
+                    ClientMsg::MuteOpponent { muted } => {
+                        room_cmd(
+                            &state,
+                            &out_tx,
+                            current_room.as_deref(),
+                            RoomCmd::Mute { client_id, muted },
+                        )
+                        .await;
+                    }
+                }
+            }
+
+            Message::Pong(payload) => {
+                let Ok(sent) = <[u8; 8]>::try_from(payload.as_slice()) else {
+                    continue;
+                };
+                let now = epoch.elapsed().as_micros() as u64;
+                let rtt_ms = now.saturating_sub(u64::from_be_bytes(sent)) / 1000;
+                let _ = out_tx.send(ServerMsg::Latency { rtt_ms });
+            }
+
+            Message::Binary(_) => {
+                let _ = out_tx.send(ServerMsg::Error {
+                    message: "Binary messages are not supported".to_string(),
+                });
+            }
+
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    if let Some(task) = lobby_task {
+        task.abort();
+    }
+
+    state.matchmaker.cancel(client_id);
+    state.clients.unregister(client_id);
+
+    if let Some(r) = current_room {
+        let _ = state.manager.disconnect(&r, client_id).await;
+    }
+
+    drop(out_tx);
+    if tokio::time::timeout(CLOSE_GRACE, &mut sender)
+        .await
+        .is_err()
+    {
+        sender.abort();
+    }
+    state.sockets.send_modify(|n| *n -= 1);
+}
+
+fn reject(
+    out_tx: &Outbox,
+    strikes: &mut TokenBucket,
+    client_id: Uuid,
+    limit: Limit,
+    message: String,
+) -> bool {
+    let _ = out_tx.send(ServerMsg::LimitExceeded { limit, message });
+    strike(strikes, client_id)
+}
+
+fn strike(strikes: &mut TokenBucket, client_id: Uuid) -> bool {
+    if strikes.try_take() {
+        return false;
+    }
+    tracing::warn!("Disconnecting {}: too many limit violations", client_id);
+    true
+}
+
+async fn room_cmd(state: &AppState, out_tx: &Outbox, room_id: Option<&str>, cmd: RoomCmd) {
+    let result = match room_id {
+        Some(r) => state.manager.send_cmd(r, cmd).await,
+        None => Err("Not in a room".to_string()),
+    };
+    if let Err(message) = result {
+        let _ = out_tx.send(ServerMsg::Error { message });
+    }
+}
+
+async fn forward_lobby(
+    state: AppState,
+    mut lobby_rx: broadcast::Receiver<ServerMsg>,
+    out_tx: Outbox,
+) {
+    loop {
+        let msg = match lobby_rx.recv().await {
+            Ok(msg) => msg,
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                let rooms = state.manager.list_rooms().await;
+                ServerMsg::RoomList {
+                    rooms,
+                    next_cursor: None,
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        if out_tx.send(msg).is_err() {
+            break;
+        }
+    }
+}
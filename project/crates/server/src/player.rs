@@ -0,0 +1,67 @@
+use uuid::Uuid;
+
+use shared::net::Profile;
+
+use crate::archive::{Archive, ArchiveEvent};
+use crate::rating::DEFAULT_RATING;
+
+pub const MAX_NAME_LEN: usize = 24;
+
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub player_id: Uuid,
+    pub name: String,
+}
+
+impl Identity {
+    pub fn guest(client_id: Uuid) -> Self {
+        let short = client_id.simple().to_string();
+        Self {
+            player_id: client_id,
+            name: format!("Guest-{}", &short[..6]),
+        }
+    }
+}
+
+pub fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Nickname must not be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!(
+            "Nickname must be at most {} characters",
+            MAX_NAME_LEN
+        ));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Nickname contains invalid characters".to_string());
+    }
+    Ok(name.to_string())
+}
+
+pub async fn register(archive: &Archive, identity: &Identity) -> Profile {
+    archive.record(ArchiveEvent::PlayerSeen {
+        player_id: identity.player_id,
+        name: identity.name.clone(),
+    });
+
+    let stats = archive
+        .get_profile(identity.player_id.to_string())
+        .await
+        .ok()
+        .flatten();
+
+    Profile {
+        player_id: identity.player_id.to_string(),
+        name: identity.name.clone(),
+        games_played: stats.as_ref().map_or(0, |p| p.games_played),
+        games_won: stats.as_ref().map_or(0, |p| p.games_won),
+        trapper_rating: stats
+            .as_ref()
+            .map_or(DEFAULT_RATING as i32, |p| p.trapper_rating),
+        mouse_rating: stats
+            .as_ref()
+            .map_or(DEFAULT_RATING as i32, |p| p.mouse_rating),
+    }
+}
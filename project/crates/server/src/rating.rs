@@ -0,0 +1,97 @@
+use shared::types::{BotLevel, Turn};
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+pub const K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ratings {
+    pub trapper: f64,
+    pub mouse: f64,
+}
+
+impl Default for Ratings {
+    fn default() -> Self {
+        Self {
+            trapper: DEFAULT_RATING,
+            mouse: DEFAULT_RATING,
+        }
+    }
+}
+
+impl Ratings {
+    pub fn get(&self, role: Turn) -> f64 {
+        match role {
+            Turn::Trapper => self.trapper,
+            Turn::Mouse => self.mouse,
+        }
+    }
+}
+
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+pub fn elo_delta(trapper: f64, mouse: f64, score: f64) -> f64 {
+    K_FACTOR * (score - expected_score(trapper, mouse))
+}
+
+pub fn bot_level(rating: f64) -> BotLevel {
+    if rating < 1400.0 {
+        BotLevel::Easy
+    } else if rating < 1600.0 {
+        BotLevel::Medium
+    } else {
+        BotLevel::Hard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn expected_scores_add_up_to_one() {
+        assert!(close(expected_score(1500.0, 1500.0), 0.5));
+        assert!(close(
+            expected_score(1700.0, 1500.0) + expected_score(1500.0, 1700.0),
+            1.0
+        ));
+        assert!(close(expected_score(1900.0, 1500.0), 10.0 / 11.0));
+    }
+
+    #[test]
+    fn even_players_move_by_half_the_k_factor() {
+        assert!(close(elo_delta(1500.0, 1500.0, 1.0), K_FACTOR / 2.0));
+        assert!(close(elo_delta(1500.0, 1500.0, 0.0), -K_FACTOR / 2.0));
+        assert!(close(elo_delta(1500.0, 1500.0, 0.5), 0.0));
+    }
+
+    #[test]
+    fn upsets_move_ratings_more_than_expected_wins() {
+        let expected = elo_delta(1800.0, 1400.0, 1.0);
+        let upset = elo_delta(1400.0, 1800.0, 1.0);
+        assert!(expected > 0.0 && expected < upset && upset < K_FACTOR);
+        assert!(close(
+            elo_delta(1800.0, 1400.0, 1.0) - elo_delta(1800.0, 1400.0, 0.0),
+            K_FACTOR
+        ));
+    }
+
+    #[test]
+    fn draws_favour_the_lower_rated_side() {
+        assert!(elo_delta(1400.0, 1600.0, 0.5) > 0.0);
+        assert!(elo_delta(1600.0, 1400.0, 0.5) < 0.0);
+    }
+
+    #[test]
+    fn bot_level_follows_rating_bands() {
+        assert_eq!(bot_level(1399.0), BotLevel::Easy);
+        assert_eq!(bot_level(1400.0), BotLevel::Medium);
+        assert_eq!(bot_level(1599.0), BotLevel::Medium);
+        assert_eq!(bot_level(1600.0), BotLevel::Hard);
+    }
+}
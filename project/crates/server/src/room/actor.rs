@@ -0,0 +1,2057 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio::time::{sleep_until, Instant};
+use uuid::Uuid;
+
+use shared::ai;
+use shared::hex::inside_board;
+use shared::net::{RolePreference, RoomInfo, ServerMsg};
+use shared::rules::{apply_action, GameError, MAX_RADIUS, MIN_RADIUS};
+use shared::types::{
+    AbandonPolicy, Action, BoardConfig, BotLevel, ClockState, Coord, GameState, GameStatus,
+    TimeControl, TimeoutPolicy, Turn,
+};
+
+use crate::archive::{Archive, ArchiveEvent};
+use crate::auth::verify_password;
+use crate::metrics::Metrics;
+use crate::net::outbox::Outbox;
+use crate::player::Identity;
+use crate::rating::{bot_level, Ratings};
+use crate::room::clock::{self, Clock};
+use crate::room::journal::{Journal, RoomEvent};
+
+const INITIAL_BLOCKS: usize = 8;
+const ABANDON_GRACE: Duration = Duration::from_secs(30);
+const EMPTY_ROOM_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_PASSWORD_LEN: usize = 64;
+pub const MAX_ROOM_NAME_LEN: usize = 48;
+pub const WRONG_PASSWORD: &str = "Wrong room password";
+const MAX_CHAT_LEN: usize = 300;
+const CHAT_BURST: usize = 5;
+const CHAT_WINDOW: Duration = Duration::from_secs(10);
+const ROOM_QUEUE_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomConfig {
+    pub vs_bot: bool,
+    pub radius: i32,
+    pub time_control: Option<TimeControl>,
+    pub on_timeout: TimeoutPolicy,
+    pub on_abandon: AbandonPolicy,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(skip)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub password_hash: Option<String>,
+    #[serde(default)]
+    pub invited_player: Option<Uuid>,
+}
+
+impl RoomConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(p) = self.password.as_deref() {
+            if p.is_empty() || p.chars().count() > MAX_PASSWORD_LEN {
+                return Err(format!(
+                    "Room password must be 1 to {} characters",
+                    MAX_PASSWORD_LEN
+                ));
+            }
+        }
+        if !(MIN_RADIUS..=MAX_RADIUS).contains(&self.radius) {
+            return Err(format!(
+                "Board radius must be between {} and {}",
+                MIN_RADIUS, MAX_RADIUS
+            ));
+        }
+        if let Some(tc) = self.time_control.as_ref() {
+            clock::validate(tc)?;
+        }
+        if self.on_timeout == TimeoutPolicy::AutoMove
+            && !matches!(self.time_control, Some(TimeControl::PerMove { .. }))
+        {
+            return Err("Auto-move on timeout requires a per-move time control".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_room_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Room name must not be empty".to_string());
+    }
+    if name.chars().count() > MAX_ROOM_NAME_LEN {
+        return Err(format!(
+            "Room name must be at most {} characters",
+            MAX_ROOM_NAME_LEN
+        ));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Room name contains invalid characters".to_string());
+    }
+    Ok(name.to_string())
+}
+
+#[derive(Clone)]
+pub struct RoomServices {
+    pub archive: Archive,
+    pub journal: Journal,
+    pub metrics: Metrics,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomSnapshot {
+    pub players: u8,
+    pub trapper: Option<String>,
+    pub mouse: Option<String>,
+    pub started: bool,
+    pub finished: bool,
+    pub radius: i32,
+    pub time_control: Option<TimeControl>,
+    pub on_timeout: TimeoutPolicy,
+    pub on_abandon: AbandonPolicy,
+}
+
+#[derive(Clone)]
+pub struct RoomHandle {
+    pub room_id: String,
+    pub name: String,
+    pub config: RoomConfig,
+    pub seq: u64,
+    pub invite_code: Option<String>,
+    cmd_tx: mpsc::Sender<(Instant, RoomCmd)>,
+    pub snapshot_rx: watch::Receiver<RoomSnapshot>,
+    members: Arc<Mutex<Vec<(Uuid, Outbox)>>>,
+    task: AbortHandle,
+}
+
+impl RoomHandle {
+    pub fn info(&self) -> RoomInfo {
+        let snap = self.snapshot_rx.borrow().clone();
+        RoomInfo {
+            room_id: self.room_id.clone(),
+            name: self.name.clone(),
+            players: snap.players,
+            trapper: snap.trapper,
+            mouse: snap.mouse,
+            vs_bot: self.config.vs_bot,
+            started: snap.started,
+            radius: snap.radius,
+            time_control: snap.time_control,
+            on_timeout: snap.on_timeout,
+            on_abandon: snap.on_abandon,
+            private: self.config.private,
+            locked: self.config.password_hash.is_some(),
+            invite_code: self.invite_code.clone(),
+        }
+    }
+
+    pub fn members(&self) -> Vec<(Uuid, Outbox)> {
+        self.members
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub async fn send(&self, cmd: RoomCmd) -> Result<(), String> {
+        self.cmd_tx
+            .send((Instant::now(), cmd))
+            .await
+            .map_err(|_| "Room task is dead".to_string())
+    }
+
+    pub fn try_send(&self, cmd: RoomCmd) -> bool {
+        self.cmd_tx.try_send((Instant::now(), cmd)).is_ok()
+    }
+
+    pub fn pending_commands(&self) -> usize {
+        self.cmd_tx.max_capacity() - self.cmd_tx.capacity()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.cmd_tx.is_closed()
+    }
+
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    pub fn is_same(&self, other: &RoomHandle) -> bool {
+        self.cmd_tx.same_channel(&other.cmd_tx)
+    }
+
+    pub fn authorize(&self, secret: Option<&str>) -> Result<(), String> {
+        let code_ok = self
+            .invite_code
+            .as_deref()
+            .zip(secret)
+            .is_some_and(|(code, s)| code.eq_ignore_ascii_case(s.trim()));
+        let password_ok = self
+            .config
+            .password_hash
+            .as_deref()
+            .zip(secret)
+            .is_some_and(|(hash, s)| verify_password(s, hash));
+        if code_ok || password_ok {
+            return Ok(());
+        }
+
+        if self.config.password_hash.is_some() {
+            return Err(WRONG_PASSWORD.to_string());
+        }
+        if self.config.private {
+            return Err("This room is private".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub enum RoomCmd {
+    Join {
+        client_id: Uuid,
+        identity: Identity,
+        ratings: Ratings,
+        role: Option<RolePreference>,
+        client_tx: Outbox,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    Rejoin {
+        client_id: Uuid,
+        identity: Identity,
+        token: String,
+        client_tx: Outbox,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    Leave {
+        client_id: Uuid,
+    },
+    Disconnect {
+        client_id: Uuid,
+    },
+    Action {
+        client_id: Uuid,
+        action: Action,
+    },
+    RequestRematch {
+        client_id: Uuid,
+        swap_roles: bool,
+    },
+    AcceptRematch {
+        client_id: Uuid,
+    },
+    Resign {
+        client_id: Uuid,
+    },
+    OfferDraw {
+        client_id: Uuid,
+    },
+    AcceptDraw {
+        client_id: Uuid,
+    },
+    Abort {
+        client_id: Uuid,
+    },
+    Ready {
+        client_id: Uuid,
+    },
+    UpdateSettings {
+        client_id: Uuid,
+        radius: i32,
+        time_control: Option<TimeControl>,
+        on_timeout: TimeoutPolicy,
+        on_abandon: AbandonPolicy,
+    },
+    SwapRoles {
+        client_id: Uuid,
+    },
+    Kick {
+        client_id: Uuid,
+        seat: Turn,
+    },
+    Chat {
+        client_id: Uuid,
+        text: String,
+    },
+    Mute {
+        client_id: Uuid,
+        muted: bool,
+    },
+    Inspect {
+        reply: oneshot::Sender<RoomDetails>,
+    },
+    Close {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatDetails {
+    pub seat: Turn,
+    pub client_id: Uuid,
+    pub player_id: Uuid,
+    pub name: String,
+    pub bot: bool,
+    pub absent_secs: Option<u64>,
+    pub ready: bool,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomDetails {
+    pub config: RoomConfig,
+    pub host: Option<Turn>,
+    pub started: bool,
+    pub uptime_secs: u64,
+    pub game_id: Option<Uuid>,
+    pub moves_played: u32,
+    pub state: Option<GameState>,
+    pub clock: Option<ClockState>,
+    pub players: Vec<SeatDetails>,
+}
+
+impl RoomCmd {
+    fn name(&self) -> &'static str {
+        match self {
+            RoomCmd::Join { .. } => "join",
+            RoomCmd::Rejoin { .. } => "rejoin",
+            RoomCmd::Leave { .. } => "leave",
+            RoomCmd::Disconnect { .. } => "disconnect",
+            RoomCmd::Action { .. } => "action",
+            RoomCmd::RequestRematch { .. } => "request_rematch",
+            RoomCmd::AcceptRematch { .. } => "accept_rematch",
+            RoomCmd::Resign { .. } => "resign",
+            RoomCmd::OfferDraw { .. } => "offer_draw",
+            RoomCmd::AcceptDraw { .. } => "accept_draw",
+            RoomCmd::Abort { .. } => "abort",
+            RoomCmd::Ready { .. } => "ready",
+            RoomCmd::UpdateSettings { .. } => "update_settings",
+            RoomCmd::SwapRoles { .. } => "swap_roles",
+            RoomCmd::Kick { .. } => "kick",
+            RoomCmd::Chat { .. } => "chat",
+            RoomCmd::Mute { .. } => "mute",
+            RoomCmd::Inspect { .. } => "inspect",
+            RoomCmd::Close { .. } => "close",
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Player {
+    id: Uuid,
+    player_id: Uuid,
+    name: String,
+    tx: Outbox,
+    token: Uuid,
+    absent_since: Option<Instant>,
+    bot: bool,
+    muted: bool,
+    chat_sent: VecDeque<Instant>,
+}
+
+struct RematchOffer {
+    from: Uuid,
+    swap_roles: bool,
+}
+
+struct Room {
+    room_id: String,
+    config: RoomConfig,
+    trapper: Option<Player>,
+    mouse: Option<Player>,
+    started: bool,
+    game_id: Uuid,
+    state: Option<GameState>,
+    clock: Option<Clock>,
+    moves_played: u32,
+    draw_offer: Option<Uuid>,
+    rematch: Option<RematchOffer>,
+    wins: HashMap<Uuid, u32>,
+    bot_level: Option<BotLevel>,
+    host: Option<Uuid>,
+    ready: HashSet<Uuid>,
+    banned: HashSet<Uuid>,
+    snapshot_tx: watch::Sender<RoomSnapshot>,
+    members: Arc<Mutex<Vec<(Uuid, Outbox)>>>,
+    services: RoomServices,
+    closed: bool,
+    empty_since: Option<Instant>,
+    created: Instant,
+}
+
+pub fn spawn_room(
+    room_id: String,
+    name: String,
+    config: RoomConfig,
+    seq: u64,
+    invite_code: Option<String>,
+    services: RoomServices,
+) -> (RoomHandle, JoinHandle<()>) {
+    let (room, snapshot_rx) = Room::new(room_id, config, services);
+    room.log(RoomEvent::Created {
+        name: name.clone(),
+        config: room.config.clone(),
+        invite_code: invite_code.clone(),
+    });
+
+    launch(room, name, seq, invite_code, snapshot_rx)
+}
+
+pub fn restore_room(
+    room_id: String,
+    seq: u64,
+    history: Vec<RoomEvent>,
+    members: Vec<(Uuid, Outbox)>,
+    services: RoomServices,
+) -> Option<(RoomHandle, JoinHandle<()>)> {
+    let mut events = history.into_iter();
+    let Some(RoomEvent::Created {
+        name,
+        config,
+        invite_code,
+    }) = events.next()
+    else {
+        tracing::warn!("Room {} has no creation event, dropping it", room_id);
+        return None;
+    };
+
+    let (mut room, snapshot_rx) = Room::new(room_id, config, services);
+    for event in events {
+        room.replay(event);
+    }
+
+    if !room.game_running() {
+        return None;
+    }
+
+    let now = Instant::now();
+    let mut returning = Vec::new();
+    for p in [room.trapper.as_mut(), room.mouse.as_mut()]
+        .into_iter()
+        .flatten()
+        .filter(|p| !p.bot)
+    {
+        match members.iter().find(|(id, _)| *id == p.id) {
+            Some((_, tx)) => {
+                p.tx = tx.clone();
+                returning.push(p.id);
+            }
+            None => p.absent_since = Some(now),
+        }
+    }
+    if let (Some(c), Some(gs)) = (room.clock.as_mut(), room.state.as_ref()) {
+        c.start(gs.turn, now);
+    }
+
+    room.update_snapshot();
+    for seat in returning.into_iter().filter_map(|id| room.seat_of(id)) {
+        room.send_game_start(seat);
+    }
+    room.play_bots();
+
+    tracing::info!(
+        "Restored room {} ({}) after {} moves",
+        room.room_id,
+        name,
+        room.moves_played
+    );
+
+    Some(launch(room, name, seq, invite_code, snapshot_rx))
+}
+
+fn launch(
+    room: Room,
+    name: String,
+    seq: u64,
+    invite_code: Option<String>,
+    snapshot_rx: watch::Receiver<RoomSnapshot>,
+) -> (RoomHandle, JoinHandle<()>) {
+    let (cmd_tx, cmd_rx) = mpsc::channel(ROOM_QUEUE_CAPACITY);
+    let room_id = room.room_id.clone();
+    let config = room.config.clone();
+    let members = room.members.clone();
+
+    let task = tokio::spawn(room_loop(room, name.clone(), cmd_rx));
+
+    let handle = RoomHandle {
+        room_id,
+        name,
+        config,
+        seq,
+        invite_code,
+        cmd_tx,
+        snapshot_rx,
+        members,
+        task: task.abort_handle(),
+    };
+    (handle, task)
+}
+
+async fn room_loop(mut room: Room, name: String, mut cmd_rx: mpsc::Receiver<(Instant, RoomCmd)>) {
+    loop {
+        room.sync_members();
+        room.track_empty();
+        if room.closed {
+            break;
+        }
+
+        let deadline = room.next_deadline();
+        let cmd = tokio::select! {
+            cmd = cmd_rx.recv() => cmd,
+            _ = sleep_until_opt(deadline) => {
+                room.on_timer();
+                continue;
+            }
+        };
+        let Some((queued, cmd)) = cmd else {
+            break;
+        };
+
+        let received = Instant::now();
+        let command = cmd.name();
+        room.services
+            .metrics
+            .command_wait_seconds
+            .with_label_values(&[command])
+            .observe((received - queued).as_secs_f64());
+
+        match cmd {
+            RoomCmd::Join {
+                client_id,
+                identity,
+                ratings,
+                role,
+                client_tx,
+                reply,
+            } => {
+                let _ = reply.send(room.join(client_id, identity, ratings, role, client_tx));
+            }
+
+            RoomCmd::Rejoin {
+                client_id,
+                identity,
+                token,
+                client_tx,
+                reply,
+            } => {
+                let _ = reply.send(room.rejoin(client_id, identity, &token, client_tx));
+            }
+
+            RoomCmd::Leave { client_id } => {
+                room.leave(client_id);
+            }
+
+            RoomCmd::Disconnect { client_id } => {
+                room.disconnect(client_id);
+            }
+
+            RoomCmd::Action { client_id, action } => {
+                if let Err(message) = room.action(client_id, action) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::RequestRematch {
+                client_id,
+                swap_roles,
+            } => {
+                if let Err(message) = room.request_rematch(client_id, swap_roles) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::AcceptRematch { client_id } => {
+                if let Err(message) = room.accept_rematch(client_id) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::Resign { client_id } => {
+                if let Err(message) = room.resign(client_id) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::OfferDraw { client_id } => {
+                if let Err(message) = room.offer_draw(client_id) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::AcceptDraw { client_id } => {
+                if let Err(message) = room.accept_draw(client_id) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::Abort { client_id } => {
+                if let Err(message) = room.abort(client_id) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::Ready { client_id } => {
+                if let Err(message) = room.set_ready(client_id) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::UpdateSettings {
+                client_id,
+                radius,
+                time_control,
+                on_timeout,
+                on_abandon,
+            } => {
+                let result = room.host_only(client_id).and_then(|()| {
+                    room.update_settings(radius, time_control, on_timeout, on_abandon)
+                });
+                if let Err(message) = result {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::SwapRoles { client_id } => match room.host_only(client_id) {
+                Ok(()) => room.swap_roles(),
+                Err(message) => room.send_to(client_id, ServerMsg::Error { message }),
+            },
+
+            RoomCmd::Kick { client_id, seat } => {
+                if let Err(message) = room.kick(client_id, seat) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::Chat { client_id, text } => {
+                if let Err(message) = room.chat(client_id, &text) {
+                    room.send_to(client_id, ServerMsg::Error { message });
+                }
+            }
+
+            RoomCmd::Mute { client_id, muted } => {
+                if let Some(seat) = room.seat_of(client_id) {
+                    if let Some(p) = room.player_mut(seat) {
+                        p.muted = muted;
+                    }
+                }
+            }
+
+            RoomCmd::Inspect { reply } => {
+                let _ = reply.send(room.details());
+            }
+
+            RoomCmd::Close { reason } => {
+                room.close(reason);
+            }
+        }
+
+        room.services
+            .metrics
+            .command_seconds
+            .with_label_values(&[command])
+            .observe(received.elapsed().as_secs_f64());
+    }
+
+    room.services.journal.forget(&room.room_id);
+    tracing::info!("Room task ended: {} ({})", room.room_id, name);
+}
+
+impl Room {
+    fn new(
+        room_id: String,
+        config: RoomConfig,
+        services: RoomServices,
+    ) -> (Self, watch::Receiver<RoomSnapshot>) {
+        let (snapshot_tx, snapshot_rx) = watch::channel(RoomSnapshot {
+            players: 0,
+            trapper: None,
+            mouse: None,
+            started: false,
+            finished: false,
+            radius: config.radius,
+            time_control: config.time_control,
+            on_timeout: config.on_timeout,
+            on_abandon: config.on_abandon,
+        });
+
+        let room = Room {
+            room_id,
+            config,
+            trapper: None,
+            mouse: None,
+            started: false,
+            game_id: Uuid::nil(),
+            state: None,
+            clock: None,
+            moves_played: 0,
+            draw_offer: None,
+            rematch: None,
+            wins: HashMap::new(),
+            bot_level: None,
+            host: None,
+            ready: HashSet::new(),
+            banned: HashSet::new(),
+            snapshot_tx,
+            members: Arc::default(),
+            services,
+            closed: false,
+            empty_since: Some(Instant::now()),
+            created: Instant::now(),
+        };
+
+        (room, snapshot_rx)
+    }
+
+    fn log(&self, event: RoomEvent) {
+        self.services.journal.append(&self.room_id, event);
+    }
+
+    fn replay(&mut self, event: RoomEvent) {
+        match event {
+            RoomEvent::Created { .. } => {}
+            RoomEvent::PlayerJoined {
+                client_id,
+                seat,
+                token,
+                player_id,
+                name,
+            } => {
+                self.host.get_or_insert(client_id);
+                *self.seat_mut(seat) = Some(Player {
+                    id: client_id,
+                    player_id,
+                    name,
+                    tx: Outbox::detached(),
+                    token,
+                    absent_since: None,
+                    bot: false,
+                    muted: false,
+                    chat_sent: VecDeque::new(),
+                });
+            }
+            RoomEvent::PlayerRejoined {
+                client_id,
+                seat,
+                player_id,
+                name,
+            } => {
+                let old = self.player(seat).map(|p| p.id);
+                if let Some(wins) = old.and_then(|id| self.wins.remove(&id)) {
+                    self.wins.insert(client_id, wins);
+                }
+                if old.is_some() && self.host == old {
+                    self.host = Some(client_id);
+                }
+                if let Some(p) = self.player_mut(seat) {
+                    p.id = client_id;
+                    p.player_id = player_id;
+                    p.name = name;
+                    p.bot = false;
+                }
+            }
+            RoomEvent::PlayerLeft { seat } => {
+                *self.seat_mut(seat) = None;
+                self.reset_game();
+                self.pass_host();
+            }
+            RoomEvent::BotTookOver { seat } => {
+                if let Some(p) = self.player_mut(seat) {
+                    p.bot = true;
+                }
+            }
+            RoomEvent::BotJoined { seat, level } => {
+                self.seat_bot(seat, level);
+            }
+            RoomEvent::RolesSwapped => {
+                std::mem::swap(&mut self.trapper, &mut self.mouse);
+            }
+            RoomEvent::SettingsChanged {
+                radius,
+                time_control,
+                on_timeout,
+                on_abandon,
+            } => {
+                self.config.radius = radius;
+                self.config.time_control = time_control;
+                self.config.on_timeout = on_timeout;
+                self.config.on_abandon = on_abandon;
+            }
+            RoomEvent::GameStarted { game_id, seed } => {
+                self.started = true;
+                self.game_id = game_id;
+                self.moves_played = 0;
+                self.state = Some(make_initial_state(self.config.radius, INITIAL_BLOCKS, seed));
+                self.clock = self.config.time_control.map(Clock::new);
+            }
+            RoomEvent::ActionApplied { action, clock } => {
+                let Some(gs) = self.state.clone() else {
+                    return;
+                };
+                match apply_action(gs, action) {
+                    Ok(new_state) => {
+                        self.state = Some(new_state);
+                        self.moves_played += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Room {}: replayed action failed: {}", self.room_id, e);
+                    }
+                }
+                if let (Some(tc), Some(cs)) = (self.config.time_control, clock) {
+                    self.clock = Some(Clock::restore(tc, &cs));
+                }
+            }
+            RoomEvent::GameEnded { status } => {
+                if let Some(gs) = self.state.as_mut() {
+                    gs.status = status.clone();
+                }
+                if let Some(w) = status.winner().and_then(|seat| self.player(seat)) {
+                    *self.wins.entry(w.id).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    fn seat_mut(&mut self, seat: Turn) -> &mut Option<Player> {
+        match seat {
+            Turn::Trapper => &mut self.trapper,
+            Turn::Mouse => &mut self.mouse,
+        }
+    }
+
+    fn reset_game(&mut self) {
+        self.started = false;
+        self.state = None;
+        self.clock = None;
+        self.draw_offer = None;
+        self.rematch = None;
+        self.wins.clear();
+        self.ready.clear();
+    }
+
+    fn players(&self) -> u8 {
+        (self.trapper.is_some() as u8) + (self.mouse.is_some() as u8)
+    }
+
+    fn humans(&self) -> usize {
+        [self.trapper.as_ref(), self.mouse.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|p| !p.bot)
+            .count()
+    }
+
+    fn pass_host(&mut self) {
+        if self.host.is_some_and(|h| self.seat_of(h).is_some()) {
+            return;
+        }
+        self.host = [self.trapper.as_ref(), self.mouse.as_ref()]
+            .into_iter()
+            .flatten()
+            .find(|p| !p.bot)
+            .map(|p| p.id);
+    }
+
+    fn seat_bot(&mut self, seat: Turn, level: BotLevel) {
+        self.bot_level = Some(level);
+        *self.seat_mut(seat) = Some(Player {
+            id: Uuid::new_v4(),
+            player_id: Uuid::nil(),
+            name: format!("Bot ({:?})", level),
+            tx: Outbox::detached(),
+            token: Uuid::new_v4(),
+            absent_since: None,
+            bot: true,
+            muted: false,
+            chat_sent: VecDeque::new(),
+        });
+    }
+
+    fn seat_of(&self, who: Uuid) -> Option<Turn> {
+        if self.trapper.as_ref().is_some_and(|p| p.id == who) {
+            Some(Turn::Trapper)
+        } else if self.mouse.as_ref().is_some_and(|p| p.id == who) {
+            Some(Turn::Mouse)
+        } else {
+            None
+        }
+    }
+
+    fn player(&self, seat: Turn) -> Option<&Player> {
+        match seat {
+            Turn::Trapper => self.trapper.as_ref(),
+            Turn::Mouse => self.mouse.as_ref(),
+        }
+    }
+
+    fn player_mut(&mut self, seat: Turn) -> Option<&mut Player> {
+        match seat {
+            Turn::Trapper => self.trapper.as_mut(),
+            Turn::Mouse => self.mouse.as_mut(),
+        }
+    }
+
+    fn opponent_of(&self, who: Uuid) -> Option<&Player> {
+        self.player(self.seat_of(who)?.other())
+    }
+
+    fn seat_name(&self, seat: Turn) -> Option<String> {
+        self.player(seat).map(|p| p.name.clone())
+    }
+
+    fn game_running(&self) -> bool {
+        self.state
+            .as_ref()
+            .is_some_and(|gs| gs.status == GameStatus::Running)
+    }
+
+    fn update_snapshot(&self) {
+        let next = RoomSnapshot {
+            players: self.players(),
+            trapper: self.seat_name(Turn::Trapper),
+            mouse: self.seat_name(Turn::Mouse),
+            started: self.started,
+            finished: self.game_finished(),
+            radius: self.config.radius,
+            time_control: self.config.time_control,
+            on_timeout: self.config.on_timeout,
+            on_abandon: self.config.on_abandon,
+        };
+        self.snapshot_tx.send_if_modified(|cur| {
+            if *cur == next {
+                return false;
+            }
+            *cur = next;
+            true
+        });
+    }
+
+    fn sync_members(&self) {
+        let members = [self.trapper.as_ref(), self.mouse.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|p| !p.bot && !p.tx.is_closed())
+            .map(|p| (p.id, p.tx.clone()))
+            .collect();
+        *self.members.lock().unwrap_or_else(|e| e.into_inner()) = members;
+    }
+
+    fn broadcast(&self, msg: ServerMsg) {
+        if let Some(p) = self.trapper.as_ref() {
+            let _ = p.tx.send(msg.clone());
+        }
+        if let Some(p) = self.mouse.as_ref() {
+            let _ = p.tx.send(msg);
+        }
+    }
+
+    fn send_to(&self, who: Uuid, msg: ServerMsg) {
+        if let Some(p) = self.trapper.as_ref().filter(|p| p.id == who) {
+            let _ = p.tx.send(msg);
+        } else if let Some(p) = self.mouse.as_ref().filter(|p| p.id == who) {
+            let _ = p.tx.send(msg);
+        }
+    }
+
+    fn broadcast_lobby(&self) {
+        self.broadcast(ServerMsg::LobbyState {
+            room_id: self.room_id.clone(),
+            players: self.players(),
+            trapper: self.seat_name(Turn::Trapper),
+            mouse: self.seat_name(Turn::Mouse),
+            vs_bot: self.config.vs_bot,
+            host: self.host.and_then(|h| self.seat_of(h)),
+            ready: [Turn::Trapper, Turn::Mouse]
+                .into_iter()
+                .filter(|seat| self.seat_ready(*seat))
+                .collect(),
+            radius: self.config.radius,
+            time_control: self.config.time_control,
+            on_timeout: self.config.on_timeout,
+            on_abandon: self.config.on_abandon,
+        });
+    }
+
+    fn seat_ready(&self, seat: Turn) -> bool {
+        self.player(seat)
+            .is_some_and(|p| p.bot || self.ready.contains(&p.id))
+    }
+
+    fn host_only(&self, client_id: Uuid) -> Result<(), String> {
+        if self.host != Some(client_id) {
+            return Err("Only the room host can do that".to_string());
+        }
+        if self.started {
+            return Err("Game already started in this room".to_string());
+        }
+        Ok(())
+    }
+
+    fn set_ready(&mut self, client_id: Uuid) -> Result<(), String> {
+        if self.seat_of(client_id).is_none() {
+            return Err("Not a player in this room".to_string());
+        }
+        if self.started {
+            return Err("Game already started in this room".to_string());
+        }
+
+        self.ready.insert(client_id);
+        self.broadcast_lobby();
+
+        if self.seat_ready(Turn::Trapper) && self.seat_ready(Turn::Mouse) {
+            self.start_game();
+        }
+        Ok(())
+    }
+
+    fn update_settings(
+        &mut self,
+        radius: i32,
+        time_control: Option<TimeControl>,
+        on_timeout: TimeoutPolicy,
+        on_abandon: AbandonPolicy,
+    ) -> Result<(), String> {
+        let config = RoomConfig {
+            radius,
+            time_control,
+            on_timeout,
+            on_abandon,
+            ..self.config.clone()
+        };
+        config.validate()?;
+
+        self.config = config;
+        self.log(RoomEvent::SettingsChanged {
+            radius,
+            time_control,
+            on_timeout,
+            on_abandon,
+        });
+        self.ready.clear();
+        self.update_snapshot();
+        self.broadcast_lobby();
+        Ok(())
+    }
+
+    fn swap_roles(&mut self) {
+        std::mem::swap(&mut self.trapper, &mut self.mouse);
+        self.log(RoomEvent::RolesSwapped);
+        self.ready.clear();
+        self.update_snapshot();
+        self.broadcast_lobby();
+    }
+
+    fn kick(&mut self, client_id: Uuid, seat: Turn) -> Result<(), String> {
+        self.host_only(client_id)?;
+
+        let Some(target) = self.player(seat) else {
+            return Err("That seat is empty".to_string());
+        };
+        if target.id == client_id {
+            return Err("You cannot kick yourself".to_string());
+        }
+        if target.bot {
+            return Err("The bot cannot be kicked".to_string());
+        }
+
+        let _ = target.tx.send(ServerMsg::Kicked {
+            room_id: self.room_id.clone(),
+        });
+        let (target_id, player_id) = (target.id, target.player_id);
+
+        self.banned.insert(player_id);
+        tracing::info!("Room {}: {:?} was kicked", self.room_id, seat);
+        self.leave(target_id);
+        Ok(())
+    }
+
+    fn join(
+        &mut self,
+        client_id: Uuid,
+        identity: Identity,
+        ratings: Ratings,
+        role: Option<RolePreference>,
+        client_tx: Outbox,
+    ) -> Result<(), String> {
+        if self.started {
+            return Err("Game already started in this room".to_string());
+        }
+
+        if self.seat_of(client_id).is_some() {
+            return Err("Already in this room".to_string());
+        }
+
+        if self.banned.contains(&identity.player_id) {
+            return Err("You were removed from this room".to_string());
+        }
+
+        if let Some(invited) = self.config.invited_player {
+            let seated = [self.trapper.as_ref(), self.mouse.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|p| p.player_id == invited);
+            if self.players() > 0 && !seated && identity.player_id != invited {
+                return Err("This room is reserved for another player".to_string());
+            }
+        }
+
+        let seat = pick_seat(
+            role,
+            self.trapper.is_none(),
+            self.mouse.is_none(),
+            self.config.vs_bot,
+        )?;
+
+        let token = Uuid::new_v4();
+        *self.seat_mut(seat) = Some(Player {
+            id: client_id,
+            player_id: identity.player_id,
+            name: identity.name.clone(),
+            tx: client_tx,
+            token,
+            absent_since: None,
+            bot: false,
+            muted: false,
+            chat_sent: VecDeque::new(),
+        });
+        self.log(RoomEvent::PlayerJoined {
+            client_id,
+            seat,
+            token,
+            player_id: identity.player_id,
+            name: identity.name,
+        });
+
+        if self.config.vs_bot {
+            let level = bot_level(ratings.get(seat));
+            self.seat_bot(seat.other(), level);
+            self.log(RoomEvent::BotJoined {
+                seat: seat.other(),
+                level,
+            });
+        }
+
+        self.host.get_or_insert(client_id);
+        self.ready.clear();
+        self.update_snapshot();
+        self.broadcast_lobby();
+
+        Ok(())
+    }
+
+    fn leave(&mut self, client_id: Uuid) {
+        let Some(seat) = self.seat_of(client_id) else {
+            return;
+        };
+
+        if self.game_running() {
+            self.end_game(GameStatus::Abandoned { by: seat });
+        }
+
+        *self.seat_mut(seat) = None;
+        self.reset_game();
+        self.pass_host();
+        self.log(RoomEvent::PlayerLeft { seat });
+
+        if self.humans() > 0 {
+            self.update_snapshot();
+            self.broadcast_lobby();
+        } else {
+            self.closed = true;
+        }
+    }
+
+    fn disconnect(&mut self, client_id: Uuid) {
+        let Some(seat) = self.seat_of(client_id) else {
+            return;
+        };
+
+        if !self.game_running() {
+            self.leave(client_id);
+            return;
+        }
+
+        if let Some(p) = self.player_mut(seat) {
+            p.absent_since = Some(Instant::now());
+            p.tx = Outbox::detached();
+        }
+
+        tracing::info!("Room {}: {:?} disconnected", self.room_id, seat);
+
+        if let Some(o) = self.opponent_of(client_id) {
+            let _ = o.tx.send(ServerMsg::OpponentDisconnected {
+                grace_secs: ABANDON_GRACE.as_secs(),
+            });
+        }
+    }
+
+    fn rejoin(
+        &mut self,
+        client_id: Uuid,
+        identity: Identity,
+        token: &str,
+        client_tx: Outbox,
+    ) -> Result<(), String> {
+        let seat = [Turn::Trapper, Turn::Mouse]
+            .into_iter()
+            .find(|seat| {
+                self.player(*seat)
+                    .is_some_and(|p| p.token.to_string() == token)
+            })
+            .ok_or_else(|| "Invalid rejoin token".to_string())?;
+
+        if !self.game_running() {
+            return Err("Game is no longer running".to_string());
+        }
+
+        let Some(p) = self.player_mut(seat) else {
+            return Err("Invalid rejoin token".to_string());
+        };
+        if p.absent_since.is_none() && !p.bot {
+            return Err("Seat is still connected".to_string());
+        }
+
+        let old_id = p.id;
+        p.id = client_id;
+        p.player_id = identity.player_id;
+        p.name = identity.name.clone();
+        p.tx = client_tx;
+        p.absent_since = None;
+        p.bot = false;
+
+        if let Some(wins) = self.wins.remove(&old_id) {
+            self.wins.insert(client_id, wins);
+        }
+        if self.host == Some(old_id) {
+            self.host = Some(client_id);
+        }
+        self.log(RoomEvent::PlayerRejoined {
+            client_id,
+            seat,
+            player_id: identity.player_id,
+            name: identity.name,
+        });
+        self.update_snapshot();
+
+        tracing::info!("Room {}: {:?} returned", self.room_id, seat);
+
+        self.send_game_start(seat);
+        if let Some(o) = self.opponent_of(client_id) {
+            let _ = o.tx.send(ServerMsg::OpponentReturned);
+        }
+
+        Ok(())
+    }
+
+    fn abandon_deadline(&self) -> Option<Instant> {
+        if !self.game_running() {
+            return None;
+        }
+
+        [self.trapper.as_ref(), self.mouse.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|p| !p.bot)
+            .filter_map(|p| p.absent_since)
+            .map(|since| since + ABANDON_GRACE)
+            .min()
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        let empty = self.empty_since.map(|since| since + EMPTY_ROOM_TIMEOUT);
+        [self.clock_deadline(), self.abandon_deadline(), empty]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    fn on_timer(&mut self) {
+        self.check_flag();
+        self.check_abandoned();
+        self.check_empty();
+    }
+
+    fn track_empty(&mut self) {
+        if self.humans() > 0 {
+            self.empty_since = None;
+        } else if self.empty_since.is_none() {
+            self.empty_since = Some(Instant::now());
+        }
+    }
+
+    fn check_empty(&mut self) {
+        if self
+            .empty_since
+            .is_none_or(|since| since + EMPTY_ROOM_TIMEOUT > Instant::now())
+        {
+            return;
+        }
+
+        tracing::info!("Room {} was closed after staying empty", self.room_id);
+        self.closed = true;
+    }
+
+    fn check_abandoned(&mut self) {
+        let now = Instant::now();
+
+        for seat in [Turn::Trapper, Turn::Mouse] {
+            if !self.game_running() {
+                return;
+            }
+
+            let expired = self.player(seat).is_some_and(|p| {
+                !p.bot
+                    && p.absent_since
+                        .is_some_and(|since| since + ABANDON_GRACE <= now)
+            });
+            if !expired {
+                continue;
+            }
+
+            let (opponent_present, opponent_human) = self
+                .player(seat.other())
+                .map_or((false, false), |o| (o.absent_since.is_none(), !o.bot));
+
+            if self.config.on_abandon == AbandonPolicy::BotTakeover
+                && opponent_present
+                && opponent_human
+            {
+                if let Some(p) = self.player_mut(seat) {
+                    p.bot = true;
+                }
+                self.log(RoomEvent::BotTookOver { seat });
+                tracing::info!("Room {}: bot took over {:?}", self.room_id, seat);
+                self.broadcast(ServerMsg::BotTookOver { side: seat });
+                self.play_bots();
+            } else if opponent_present {
+                self.end_game(GameStatus::Abandoned { by: seat });
+            } else {
+                self.end_game(GameStatus::Aborted);
+            }
+        }
+    }
+
+    fn play_bots(&mut self) {
+        while let Some(gs) = self.state.clone() {
+            if gs.status != GameStatus::Running {
+                break;
+            }
+            if !self.player(gs.turn).is_some_and(|p| p.bot) {
+                break;
+            }
+            if !self.auto_move(gs.turn) {
+                self.end_game(GameStatus::Abandoned { by: gs.turn });
+                break;
+            }
+        }
+    }
+
+    fn start_game(&mut self) {
+        self.started = true;
+        self.moves_played = 0;
+        self.draw_offer = None;
+        self.rematch = None;
+
+        let seed = time_seed();
+        let gs = make_initial_state(self.config.radius, INITIAL_BLOCKS, seed);
+        self.state = Some(gs.clone());
+        self.game_id = Uuid::new_v4();
+        self.log(RoomEvent::GameStarted {
+            game_id: self.game_id,
+            seed,
+        });
+        self.services.metrics.games_started.inc();
+
+        if let (Some(t), Some(m)) = (self.trapper.as_ref(), self.mouse.as_ref()) {
+            self.services.archive.record(ArchiveEvent::GameStarted {
+                game_id: self.game_id,
+                room_id: self.room_id.clone(),
+                trapper_id: t.player_id,
+                mouse_id: m.player_id,
+                state: gs.clone(),
+            });
+        }
+
+        self.clock = self.config.time_control.map(Clock::new);
+        if let Some(c) = self.clock.as_mut() {
+            c.start(gs.turn, Instant::now());
+        }
+
+        self.update_snapshot();
+
+        self.send_game_start(Turn::Trapper);
+        self.send_game_start(Turn::Mouse);
+        self.play_bots();
+    }
+
+    fn send_game_start(&self, seat: Turn) {
+        let (Some(p), Some(gs)) = (self.player(seat), self.state.as_ref()) else {
+            return;
+        };
+
+        let _ = p.tx.send(ServerMsg::GameStart {
+            state: gs.clone(),
+            your_role: seat,
+            trapper: self.seat_name(Turn::Trapper).unwrap_or_default(),
+            mouse: self.seat_name(Turn::Mouse).unwrap_or_default(),
+            clock: self.clock_state(),
+            rejoin_token: p.token.to_string(),
+        });
+    }
+
+    fn action(&mut self, client_id: Uuid, action: Action) -> Result<(), String> {
+        let Some(gs) = self.state.clone() else {
+            return Err("Game not started".to_string());
+        };
+
+        if gs.status == GameStatus::Running && self.seat_of(client_id) != Some(gs.turn) {
+            self.services.metrics.action_rejected(&GameError::WrongTurn);
+            return Err("Not your turn".to_string());
+        }
+
+        self.apply(gs, action, false)?;
+        self.play_bots();
+        Ok(())
+    }
+
+    fn apply(&mut self, gs: GameState, action: Action, auto: bool) -> Result<(), String> {
+        let side = gs.turn;
+        let new_state = apply_action(gs, action.clone()).map_err(|e| {
+            self.services.metrics.action_rejected(&e);
+            e.to_string()
+        })?;
+        self.services.metrics.actions_applied.inc();
+        self.state = Some(new_state.clone());
+        self.moves_played += 1;
+        self.draw_offer = None;
+
+        let status = new_state.status.clone();
+        if let Some(c) = self.clock.as_mut() {
+            if status == GameStatus::Running {
+                c.switch(new_state.turn, Instant::now());
+            } else {
+                c.stop(Instant::now());
+            }
+        }
+
+        self.log(RoomEvent::ActionApplied {
+            action: action.clone(),
+            clock: self.clock_state(),
+        });
+        self.services.archive.record(ArchiveEvent::ActionApplied {
+            game_id: self.game_id,
+            ply: self.moves_played,
+            side,
+            action,
+            auto,
+        });
+
+        self.broadcast(ServerMsg::GameUpdate {
+            state: new_state,
+            clock: self.clock_state(),
+        });
+
+        if status != GameStatus::Running {
+            self.finish_game(&status);
+        }
+
+        Ok(())
+    }
+
+    fn clock_state(&self) -> Option<ClockState> {
+        self.clock.as_ref().map(|c| c.state(Instant::now()))
+    }
+
+    fn clock_deadline(&self) -> Option<Instant> {
+        if !self.game_running() {
+            return None;
+        }
+        self.clock.as_ref().and_then(Clock::deadline)
+    }
+
+    fn check_flag(&mut self) {
+        let Some(side) = self.clock.as_ref().and_then(|c| c.flagged(Instant::now())) else {
+            return;
+        };
+
+        if self.config.on_timeout == TimeoutPolicy::AutoMove && self.auto_move(side) {
+            self.play_bots();
+            return;
+        }
+
+        self.end_game(GameStatus::TimeOut { by: side });
+    }
+
+    fn auto_move(&mut self, side: Turn) -> bool {
+        let Some(gs) = self.state.clone() else {
+            return false;
+        };
+        let level = self
+            .bot_level
+            .filter(|_| self.player(side).is_some_and(|p| p.bot));
+        let action = match level {
+            Some(level) => ai::choose_action_at(&gs, level),
+            None => ai::choose_action(&gs),
+        };
+        let Some(action) = action else {
+            return false;
+        };
+
+        if level.is_none() {
+            tracing::info!("Room {}: auto-moving for {:?}", self.room_id, side);
+            self.broadcast(ServerMsg::AutoMoved {
+                side,
+                action: action.clone(),
+            });
+        }
+
+        self.apply(gs, action, true).is_ok()
+    }
+
+    fn running_seat(&self, client_id: Uuid) -> Result<Turn, String> {
+        let seat = self
+            .seat_of(client_id)
+            .ok_or_else(|| "Not a player in this room".to_string())?;
+        if !self.game_running() {
+            return Err("No game in progress".to_string());
+        }
+        Ok(seat)
+    }
+
+    fn details(&self) -> RoomDetails {
+        let now = Instant::now();
+        let players = [Turn::Trapper, Turn::Mouse]
+            .into_iter()
+            .filter_map(|seat| {
+                let p = self.player(seat)?;
+                Some(SeatDetails {
+                    seat,
+                    client_id: p.id,
+                    player_id: p.player_id,
+                    name: p.name.clone(),
+                    bot: p.bot,
+                    absent_secs: p.absent_since.map(|t| now.duration_since(t).as_secs()),
+                    ready: self.seat_ready(seat),
+                    muted: p.muted,
+                })
+            })
+            .collect();
+
+        RoomDetails {
+            config: self.config.clone(),
+            host: self.host.and_then(|h| self.seat_of(h)),
+            started: self.started,
+            uptime_secs: now.duration_since(self.created).as_secs(),
+            game_id: self.state.as_ref().map(|_| self.game_id),
+            moves_played: self.moves_played,
+            state: self.state.clone(),
+            clock: self.clock_state(),
+            players,
+        }
+    }
+
+    fn close(&mut self, reason: String) {
+        if self.game_running() {
+            self.end_game(GameStatus::Aborted);
+        }
+        self.broadcast(ServerMsg::RoomClosed {
+            room_id: self.room_id.clone(),
+            reason,
+        });
+        tracing::info!("Room {} was closed by an administrator", self.room_id);
+        self.closed = true;
+    }
+
+    fn end_game(&mut self, status: GameStatus) {
+        let Some(gs) = self.state.as_mut() else {
+            return;
+        };
+        gs.status = status.clone();
+        let gs = gs.clone();
+
+        if let Some(c) = self.clock.as_mut() {
+            c.stop(Instant::now());
+        }
+
+        self.draw_offer = None;
+        self.broadcast(ServerMsg::GameUpdate {
+            state: gs,
+            clock: self.clock_state(),
+        });
+        self.finish_game(&status);
+    }
+
+    fn resign(&mut self, client_id: Uuid) -> Result<(), String> {
+        let seat = self.running_seat(client_id)?;
+        self.end_game(GameStatus::Resigned { by: seat });
+        Ok(())
+    }
+
+    fn offer_draw(&mut self, client_id: Uuid) -> Result<(), String> {
+        self.running_seat(client_id)?;
+
+        if self.draw_offer.is_some_and(|from| from != client_id) {
+            return self.accept_draw(client_id);
+        }
+
+        self.draw_offer = Some(client_id);
+        if let Some(o) = self.opponent_of(client_id) {
+            let _ = o.tx.send(ServerMsg::DrawOffered);
+        }
+        Ok(())
+    }
+
+    fn accept_draw(&mut self, client_id: Uuid) -> Result<(), String> {
+        self.running_seat(client_id)?;
+
+        if self.draw_offer.is_none_or(|from| from == client_id) {
+            return Err("No draw offer to accept".to_string());
+        }
+
+        self.end_game(GameStatus::Draw);
+        Ok(())
+    }
+
+    fn abort(&mut self, client_id: Uuid) -> Result<(), String> {
+        let seat = self.running_seat(client_id)?;
+
+        let own_moves = match seat {
+            Turn::Trapper => self.moves_played.div_ceil(2),
+            Turn::Mouse => self.moves_played / 2,
+        };
+        if own_moves > 0 {
+            return Err("Game can only be aborted before your first move".to_string());
+        }
+
+        self.end_game(GameStatus::Aborted);
+        Ok(())
+    }
+
+    fn chat(&mut self, client_id: Uuid, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Chat message is empty".to_string());
+        }
+        if text.chars().count() > MAX_CHAT_LEN {
+            return Err(format!(
+                "Chat message is longer than {} characters",
+                MAX_CHAT_LEN
+            ));
+        }
+
+        let seat = self
+            .seat_of(client_id)
+            .ok_or_else(|| "Not seated in this room".to_string())?;
+        let Some(p) = self.player_mut(seat) else {
+            return Err("Not seated in this room".to_string());
+        };
+
+        let now = Instant::now();
+        while p
+            .chat_sent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= CHAT_WINDOW)
+        {
+            p.chat_sent.pop_front();
+        }
+        if p.chat_sent.len() >= CHAT_BURST {
+            return Err("You are sending messages too fast".to_string());
+        }
+        p.chat_sent.push_back(now);
+
+        let msg = ServerMsg::Chat {
+            from: p.name.clone(),
+            seat,
+            text: text.to_string(),
+            sent_at: unix_millis(),
+        };
+        let _ = p.tx.send(msg.clone());
+        if let Some(o) = self.player(seat.other()).filter(|o| !o.muted) {
+            let _ = o.tx.send(msg);
+        }
+        Ok(())
+    }
+
+    fn finish_game(&mut self, status: &GameStatus) {
+        self.log(RoomEvent::GameEnded {
+            status: status.clone(),
+        });
+        self.services.metrics.game_finished(status);
+        self.services.archive.record(ArchiveEvent::GameEnded {
+            game_id: self.game_id,
+            status: status.clone(),
+        });
+
+        if let Some(w) = status.winner().and_then(|seat| self.player(seat)) {
+            *self.wins.entry(w.id).or_default() += 1;
+        }
+
+        for p in [self.trapper.as_ref(), self.mouse.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            let you = self.wins.get(&p.id).copied().unwrap_or(0);
+            let opponent = self
+                .opponent_of(p.id)
+                .and_then(|o| self.wins.get(&o.id).copied())
+                .unwrap_or(0);
+            let _ = p.tx.send(ServerMsg::SeriesScore { you, opponent });
+        }
+
+        let absent: Vec<Uuid> = [self.trapper.as_ref(), self.mouse.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|p| p.absent_since.is_some())
+            .map(|p| p.id)
+            .collect();
+        for id in absent {
+            self.leave(id);
+        }
+        self.update_snapshot();
+    }
+
+    fn game_finished(&self) -> bool {
+        self.state
+            .as_ref()
+            .is_some_and(|gs| gs.status != GameStatus::Running)
+    }
+
+    fn request_rematch(&mut self, client_id: Uuid, swap_roles: bool) -> Result<(), String> {
+        if self.seat_of(client_id).is_none() {
+            return Err("Not a player in this room".to_string());
+        }
+        if !self.game_finished() {
+            return Err("Rematch is only possible after the game has ended".to_string());
+        }
+
+        if self.rematch.as_ref().is_some_and(|r| r.from != client_id) {
+            return self.accept_rematch(client_id);
+        }
+
+        if self.bot_level.is_some() && self.opponent_of(client_id).is_some_and(|o| o.bot) {
+            if swap_roles {
+                std::mem::swap(&mut self.trapper, &mut self.mouse);
+                self.log(RoomEvent::RolesSwapped);
+            }
+            self.start_game();
+            return Ok(());
+        }
+
+        self.rematch = Some(RematchOffer {
+            from: client_id,
+            swap_roles,
+        });
+
+        if let Some(o) = self.opponent_of(client_id) {
+            let _ = o.tx.send(ServerMsg::RematchOffered { swap_roles });
+        }
+
+        Ok(())
+    }
+
+    fn accept_rematch(&mut self, client_id: Uuid) -> Result<(), String> {
+        if self.seat_of(client_id).is_none() {
+            return Err("Not a player in this room".to_string());
+        }
+
+        let Some(offer) = self.rematch.take_if(|r| r.from != client_id) else {
+            return Err("No rematch offer to accept".to_string());
+        };
+
+        if offer.swap_roles {
+            std::mem::swap(&mut self.trapper, &mut self.mouse);
+            self.log(RoomEvent::RolesSwapped);
+        }
+
+        self.start_game();
+        Ok(())
+    }
+}
+
+async fn sleep_until_opt(deadline: Option<Instant>) {
+    match deadline {
+        Some(d) => sleep_until(d).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0x9E3779B97F4A7C15)
+}
+
+fn pick_seat(
+    pref: Option<RolePreference>,
+    trapper_free: bool,
+    mouse_free: bool,
+    vs_bot: bool,
+) -> Result<Turn, String> {
+    if vs_bot && !(trapper_free && mouse_free) {
+        return Err("Room is full".to_string());
+    }
+
+    match pref {
+        Some(RolePreference::Trapper) if trapper_free => Ok(Turn::Trapper),
+        Some(RolePreference::Trapper) => Err("Trapper seat is already taken".to_string()),
+        Some(RolePreference::Mouse) if mouse_free => Ok(Turn::Mouse),
+        Some(RolePreference::Mouse) => Err("Mouse seat is already taken".to_string()),
+        Some(RolePreference::Random) if trapper_free && mouse_free => {
+            if XorShift64::new(time_seed()).next_u64() & 1 == 0 {
+                Ok(Turn::Trapper)
+            } else {
+                Ok(Turn::Mouse)
+            }
+        }
+        _ if trapper_free => Ok(Turn::Trapper),
+        _ if mouse_free => Ok(Turn::Mouse),
+        _ => Err("Room is full".to_string()),
+    }
+}
+
+fn make_initial_state(radius: i32, initial_blocks: usize, seed: u64) -> GameState {
+    let cfg = BoardConfig {
+        radius,
+        initial_blocks,
+        seed,
+    };
+
+    let mouse = Coord { q: 0, r: 0 };
+
+    let mut all = Vec::new();
+    for q in -radius..=radius {
+        for r in -radius..=radius {
+            let c = Coord { q, r };
+            if inside_board(c, radius) && c != mouse {
+                all.push(c);
+            }
+        }
+    }
+
+    let mut rng = XorShift64::new(seed);
+    fisher_yates(&mut all, &mut rng);
+
+    let blocks: HashSet<Coord> = all.into_iter().take(initial_blocks).collect();
+
+    GameState {
+        cfg,
+        mouse,
+        blocks,
+        turn: Turn::Trapper,
+        status: GameStatus::Running,
+    }
+}
+
+struct XorShift64 {
+    x: u64,
+}
+
+impl XorShift64 {
+    fn new(seed: u64) -> Self {
+        Self {
+            x: if seed == 0 { 0x9E3779B97F4A7C15 } else { seed },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.x;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.x = x;
+        x
+    }
+
+    fn gen_usize(&mut self, upper_exclusive: usize) -> usize {
+        (self.next_u64() as usize) % upper_exclusive.max(1)
+    }
+}
+
+fn fisher_yates<T>(v: &mut [T], rng: &mut XorShift64) {
+    for i in (1..v.len()).rev() {
+        let j = rng.gen_usize(i + 1);
+        v.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn services() -> RoomServices {
+        RoomServices {
+            archive: Archive::open(":memory:").expect("archive opens"),
+            journal: Journal::open(":memory:").expect("journal opens").0,
+            metrics: Metrics::new(),
+        }
+    }
+
+    fn config() -> RoomConfig {
+        RoomConfig {
+            vs_bot: false,
+            radius: 5,
+            time_control: Some(TimeControl::Clock {
+                base_secs: 300,
+                increment_secs: 2,
+            }),
+            on_timeout: TimeoutPolicy::default(),
+            on_abandon: AbandonPolicy::default(),
+            private: false,
+            password: None,
+            password_hash: None,
+            invited_player: None,
+        }
+    }
+
+    fn identity(name: &str) -> Identity {
+        Identity {
+            player_id: Uuid::new_v4(),
+            name: name.to_string(),
+        }
+    }
+
+    fn seats(room: &Room) -> Vec<(Uuid, Uuid, String, Uuid, bool)> {
+        [room.trapper.as_ref(), room.mouse.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|p| (p.id, p.player_id, p.name.clone(), p.token, p.bot))
+            .collect()
+    }
+
+    #[test]
+    fn replaying_the_journal_rebuilds_the_room() {
+        let mut room = Room::new("replay".to_string(), config(), services()).0;
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let (alice, bob) = (identity("alice"), identity("bob"));
+        let trapper = Some(RolePreference::Trapper);
+        let ratings = Ratings::default();
+
+        room.join(a, alice.clone(), ratings, trapper, Outbox::detached())
+            .unwrap();
+        room.join(b, bob, ratings, None, Outbox::detached())
+            .unwrap();
+        room.swap_roles();
+        room.set_ready(a).unwrap();
+        room.set_ready(b).unwrap();
+        room.resign(b).unwrap();
+
+        room.request_rematch(a, true).unwrap();
+        room.accept_rematch(b).unwrap();
+        for _ in 0..3 {
+            let turn = room.state.as_ref().unwrap().turn;
+            assert!(room.auto_move(turn));
+        }
+
+        let token = room.player(Turn::Trapper).unwrap().token.to_string();
+        room.disconnect(a);
+        let a2 = Uuid::new_v4();
+        room.rejoin(a2, alice, &token, Outbox::detached()).unwrap();
+
+        let events = room
+            .services
+            .journal
+            .load("replay", Duration::from_secs(5))
+            .expect("journal answers");
+        let mut replayed = Room::new("replay".to_string(), config(), services()).0;
+        for event in events {
+            replayed.replay(event);
+        }
+
+        assert_eq!(seats(&replayed), seats(&room));
+        assert_eq!(replayed.player(Turn::Trapper).unwrap().id, a2);
+        assert_eq!(replayed.host, Some(a2));
+        assert_eq!(replayed.wins, HashMap::from([(a2, 1)]));
+        assert!(replayed.started);
+        assert_eq!(replayed.game_id, room.game_id);
+        assert_eq!(replayed.moves_played, 3);
+        let (live, restored) = (
+            room.state.as_ref().unwrap(),
+            replayed.state.as_ref().unwrap(),
+        );
+        assert_eq!(restored.cfg.seed, live.cfg.seed);
+        assert_eq!(restored.mouse, live.mouse);
+        assert_eq!(restored.blocks, live.blocks);
+        assert_eq!(restored.turn, live.turn);
+        assert_eq!(restored.status, live.status);
+
+        let now = Instant::now();
+        let live = room.clock.as_ref().unwrap().state(now);
+        let restored = replayed.clock.as_ref().unwrap().state(now);
+        assert!(live.trapper_ms.abs_diff(restored.trapper_ms) < 1000);
+        assert!(live.mouse_ms.abs_diff(restored.mouse_ms) < 1000);
+        assert!(restored.trapper_ms > 300_000 || restored.mouse_ms > 300_000);
+    }
+
+    #[test]
+    fn random_takes_whichever_seat_is_free() {
+        let random = Some(RolePreference::Random);
+        assert_eq!(pick_seat(random, false, true, false), Ok(Turn::Mouse));
+        assert_eq!(pick_seat(random, true, false, false), Ok(Turn::Trapper));
+        assert!(pick_seat(random, false, false, false).is_err());
+    }
+
+    #[test]
+    fn a_taken_seat_is_refused() {
+        let trapper = Some(RolePreference::Trapper);
+        let mouse = Some(RolePreference::Mouse);
+        assert!(pick_seat(trapper, false, true, false).is_err());
+        assert!(pick_seat(mouse, true, false, false).is_err());
+        assert_eq!(pick_seat(mouse, true, true, false), Ok(Turn::Mouse));
+    }
+
+    #[test]
+    fn no_preference_fills_the_trapper_seat_first() {
+        assert_eq!(pick_seat(None, true, true, false), Ok(Turn::Trapper));
+        assert_eq!(pick_seat(None, false, true, false), Ok(Turn::Mouse));
+    }
+
+    #[test]
+    fn a_bot_room_takes_one_human() {
+        assert_eq!(pick_seat(None, true, true, true), Ok(Turn::Trapper));
+        assert!(pick_seat(None, false, true, true).is_err());
+    }
+
+    #[test]
+    fn abort_is_allowed_until_the_player_moves() {
+        let config = RoomConfig {
+            vs_bot: true,
+            time_control: None,
+            ..config()
+        };
+        let mut room = Room::new("abort".to_string(), config, services()).0;
+        let human = Uuid::new_v4();
+        let mouse = Some(RolePreference::Mouse);
+        room.join(
+            human,
+            identity("carol"),
+            Ratings::default(),
+            mouse,
+            Outbox::detached(),
+        )
+        .unwrap();
+        room.set_ready(human).unwrap();
+        assert_eq!(room.moves_played, 1);
+
+        assert!(room.auto_move(Turn::Mouse));
+        assert!(room.abort(human).is_err());
+
+        room.reset_game();
+        room.set_ready(human).unwrap();
+        assert_eq!(room.moves_played, 1);
+        room.abort(human).unwrap();
+        assert_eq!(room.state.unwrap().status, GameStatus::Aborted);
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use shared::types::{ClockState, TimeControl, Turn};
+
+pub struct Clock {
+    control: TimeControl,
+    trapper: Duration,
+    mouse: Duration,
+    running: Option<(Turn, Instant)>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let initial = match control {
+            TimeControl::Clock { base_secs, .. } => Duration::from_secs(base_secs),
+            TimeControl::PerMove { secs } => Duration::from_secs(secs),
+        };
+        Self {
+            control,
+            trapper: initial,
+            mouse: initial,
+            running: None,
+        }
+    }
+
+    pub fn restore(control: TimeControl, saved: &ClockState) -> Self {
+        Self {
+            control,
+            trapper: Duration::from_millis(saved.trapper_ms),
+            mouse: Duration::from_millis(saved.mouse_ms),
+            running: None,
+        }
+    }
+
+    fn remaining_mut(&mut self, side: Turn) -> &mut Duration {
+        match side {
+            Turn::Trapper => &mut self.trapper,
+            Turn::Mouse => &mut self.mouse,
+        }
+    }
+
+    fn remaining(&self, side: Turn) -> Duration {
+        match side {
+            Turn::Trapper => self.trapper,
+            Turn::Mouse => self.mouse,
+        }
+    }
+
+    pub fn start(&mut self, side: Turn, now: Instant) {
+        if let TimeControl::PerMove { secs } = self.control {
+            *self.remaining_mut(side) = Duration::from_secs(secs);
+        }
+        self.running = Some((side, now));
+    }
+
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((side, since)) = self.running.take() {
+            let left = self.remaining(side).saturating_sub(now - since);
+            *self.remaining_mut(side) = left;
+        }
+    }
+
+    pub fn switch(&mut self, next: Turn, now: Instant) {
+        let mover = self.running.map(|(side, _)| side);
+        self.stop(now);
+
+        if let (Some(mover), TimeControl::Clock { increment_secs, .. }) = (mover, self.control) {
+            *self.remaining_mut(mover) += Duration::from_secs(increment_secs);
+        }
+
+        self.start(next, now);
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.running
+            .map(|(side, since)| since + self.remaining(side))
+    }
+
+    pub fn flagged(&self, now: Instant) -> Option<Turn> {
+        let (side, _) = self.running?;
+        self.deadline().filter(|d| *d <= now).map(|_| side)
+    }
+
+    pub fn state(&self, now: Instant) -> ClockState {
+        let left = |side: Turn| match self.running {
+            Some((running, since)) if running == side => {
+                self.remaining(side).saturating_sub(now - since)
+            }
+            _ => self.remaining(side),
+        };
+
+        ClockState {
+            trapper_ms: left(Turn::Trapper).as_millis() as u64,
+            mouse_ms: left(Turn::Mouse).as_millis() as u64,
+            ticking: self.running.map(|(side, _)| side),
+        }
+    }
+}
+
+pub fn validate(control: &TimeControl) -> Result<(), String> {
+    const MAX_SECS: u64 = 3 * 60 * 60;
+
+    let ok = match *control {
+        TimeControl::Clock {
+            base_secs,
+            increment_secs,
+        } => (1..=MAX_SECS).contains(&base_secs) && increment_secs <= MAX_SECS,
+        TimeControl::PerMove { secs } => (1..=MAX_SECS).contains(&secs),
+    };
+
+    if ok {
+        Ok(())
+    } else {
+        Err(format!(
+            "Time control must use between 1 and {} seconds",
+            MAX_SECS
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn only_the_running_side_loses_time() {
+        let t0 = Instant::now();
+        let mut clock = Clock::new(TimeControl::Clock {
+            base_secs: 60,
+            increment_secs: 0,
+        });
+        clock.start(Turn::Trapper, t0);
+
+        let state = clock.state(t0 + secs(10));
+        assert_eq!(state.trapper_ms, 50_000);
+        assert_eq!(state.mouse_ms, 60_000);
+        assert_eq!(state.ticking, Some(Turn::Trapper));
+    }
+
+    #[test]
+    fn switch_adds_the_increment_to_the_mover() {
+        let t0 = Instant::now();
+        let mut clock = Clock::new(TimeControl::Clock {
+            base_secs: 60,
+            increment_secs: 2,
+        });
+        clock.start(Turn::Trapper, t0);
+        clock.switch(Turn::Mouse, t0 + secs(10));
+
+        let state = clock.state(t0 + secs(10));
+        assert_eq!(state.trapper_ms, 52_000);
+        assert_eq!(state.mouse_ms, 60_000);
+        assert_eq!(state.ticking, Some(Turn::Mouse));
+    }
+
+    #[test]
+    fn per_move_budget_resets_every_turn() {
+        let t0 = Instant::now();
+        let mut clock = Clock::new(TimeControl::PerMove { secs: 15 });
+        clock.start(Turn::Trapper, t0);
+        clock.switch(Turn::Mouse, t0 + secs(10));
+        clock.switch(Turn::Trapper, t0 + secs(12));
+
+        let state = clock.state(t0 + secs(12));
+        assert_eq!(state.trapper_ms, 15_000);
+        assert_eq!(clock.deadline(), Some(t0 + secs(27)));
+    }
+
+    #[test]
+    fn flags_the_running_side_at_the_deadline() {
+        let t0 = Instant::now();
+        let mut clock = Clock::new(TimeControl::PerMove { secs: 5 });
+        clock.start(Turn::Mouse, t0);
+
+        assert_eq!(clock.flagged(t0 + secs(4)), None);
+        assert_eq!(clock.flagged(t0 + secs(5)), Some(Turn::Mouse));
+
+        clock.stop(t0 + secs(6));
+        assert_eq!(clock.flagged(t0 + secs(6)), None);
+        assert_eq!(clock.state(t0 + secs(6)).mouse_ms, 0);
+    }
+
+    #[test]
+    fn restore_keeps_the_saved_times() {
+        let saved = ClockState {
+            trapper_ms: 1_500,
+            mouse_ms: 42_000,
+            ticking: None,
+        };
+        let clock = Clock::restore(
+            TimeControl::Clock {
+                base_secs: 60,
+                increment_secs: 0,
+            },
+            &saved,
+        );
+
+        let state = clock.state(Instant::now());
+        assert_eq!(state.trapper_ms, 1_500);
+        assert_eq!(state.mouse_ms, 42_000);
+        assert_eq!(state.ticking, None);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_controls() {
+        assert!(validate(&TimeControl::PerMove { secs: 0 }).is_err());
+        assert!(validate(&TimeControl::PerMove { secs: 4 * 60 * 60 }).is_err());
+        assert!(validate(&TimeControl::Clock {
+            base_secs: 180,
+            increment_secs: 2,
+        })
+        .is_ok());
+    }
+}
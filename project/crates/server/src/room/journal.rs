@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use shared::types::{
+    AbandonPolicy, Action, BotLevel, ClockState, GameStatus, TimeControl, TimeoutPolicy, Turn,
+};
+
+use crate::room::actor::RoomConfig;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS room_events (
+    id       INTEGER PRIMARY KEY AUTOINCREMENT,
+    room_id  TEXT NOT NULL,
+    event    TEXT NOT NULL,
+    at       INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS room_events_room ON room_events (room_id);
+";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RoomEvent {
+    Created {
+        name: String,
+        config: RoomConfig,
+        #[serde(default)]
+        invite_code: Option<String>,
+    },
+    PlayerJoined {
+        client_id: Uuid,
+        seat: Turn,
+        token: Uuid,
+        #[serde(default)]
+        player_id: Uuid,
+        #[serde(default)]
+        name: String,
+    },
+    PlayerRejoined {
+        client_id: Uuid,
+        seat: Turn,
+        #[serde(default)]
+        player_id: Uuid,
+        #[serde(default)]
+        name: String,
+    },
+    PlayerLeft {
+        seat: Turn,
+    },
+    BotTookOver {
+        seat: Turn,
+    },
+    BotJoined {
+        seat: Turn,
+        level: BotLevel,
+    },
+    RolesSwapped,
+    SettingsChanged {
+        radius: i32,
+        time_control: Option<TimeControl>,
+        on_timeout: TimeoutPolicy,
+        on_abandon: AbandonPolicy,
+    },
+    GameStarted {
+        game_id: Uuid,
+        seed: u64,
+    },
+    ActionApplied {
+        action: Action,
+        clock: Option<ClockState>,
+    },
+    GameEnded {
+        status: GameStatus,
+    },
+}
+
+pub struct SavedRoom {
+    pub room_id: String,
+    pub events: Vec<RoomEvent>,
+}
+
+enum JournalOp {
+    Append {
+        room_id: String,
+        event: RoomEvent,
+    },
+    Forget {
+        room_id: String,
+    },
+    Load {
+        room_id: String,
+        reply: mpsc::Sender<Vec<RoomEvent>>,
+    },
+    Flush(mpsc::Sender<()>),
+}
+
+#[derive(Clone)]
+pub struct Journal {
+    ops_tx: mpsc::Sender<JournalOp>,
+}
+
+impl Journal {
+    pub fn open(path: &str) -> rusqlite::Result<(Self, Vec<SavedRoom>)> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.execute_batch(SCHEMA)?;
+
+        let rooms = load_rooms(&conn)?;
+
+        let (ops_tx, ops_rx) = mpsc::channel();
+        std::thread::spawn(move || write_loop(conn, ops_rx));
+
+        Ok((Self { ops_tx }, rooms))
+    }
+
+    pub fn append(&self, room_id: &str, event: RoomEvent) {
+        let op = JournalOp::Append {
+            room_id: room_id.to_string(),
+            event,
+        };
+        if self.ops_tx.send(op).is_err() {
+            tracing::error!(
+                "Journal writer is gone, dropping event for room {}",
+                room_id
+            );
+        }
+    }
+
+    pub fn forget(&self, room_id: &str) {
+        let _ = self.ops_tx.send(JournalOp::Forget {
+            room_id: room_id.to_string(),
+        });
+    }
+
+    pub fn load(&self, room_id: &str, timeout: Duration) -> Option<Vec<RoomEvent>> {
+        let (reply, events_rx) = mpsc::channel();
+        let op = JournalOp::Load {
+            room_id: room_id.to_string(),
+            reply,
+        };
+        self.ops_tx.send(op).ok()?;
+        events_rx.recv_timeout(timeout).ok()
+    }
+
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let (done_tx, done_rx) = mpsc::channel();
+        self.ops_tx.send(JournalOp::Flush(done_tx)).is_ok() && done_rx.recv_timeout(timeout).is_ok()
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn write_loop(conn: Connection, ops_rx: mpsc::Receiver<JournalOp>) {
+    while let Ok(op) = ops_rx.recv() {
+        let res = match op {
+            JournalOp::Append { room_id, event } => {
+                let text = serde_json::to_string(&event).unwrap_or_default();
+                conn.execute(
+                    "INSERT INTO room_events (room_id, event, at) VALUES (?1, ?2, ?3)",
+                    params![room_id, text, now_ms()],
+                )
+            }
+            JournalOp::Forget { room_id } => conn.execute(
+                "DELETE FROM room_events WHERE room_id = ?1",
+                params![room_id],
+            ),
+            JournalOp::Load { room_id, reply } => {
+                match load_room(&conn, &room_id) {
+                    Ok(events) => {
+                        let _ = reply.send(events);
+                    }
+                    Err(e) => tracing::error!("Failed to read room journal: {}", e),
+                }
+                continue;
+            }
+            JournalOp::Flush(done_tx) => {
+                let _ = done_tx.send(());
+                continue;
+            }
+        };
+
+        if let Err(e) = res {
+            tracing::error!("Failed to write room journal: {}", e);
+        }
+    }
+}
+
+fn load_rooms(conn: &Connection) -> rusqlite::Result<Vec<SavedRoom>> {
+    let mut stmt = conn.prepare("SELECT room_id, event FROM room_events ORDER BY id")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut rooms: Vec<SavedRoom> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let (room_id, text) = row?;
+        let event = match serde_json::from_str::<RoomEvent>(&text) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("Skipping unreadable event for room {}: {}", room_id, e);
+                continue;
+            }
+        };
+
+        match index.get(&room_id) {
+            Some(&i) => rooms[i].events.push(event),
+            None => {
+                index.insert(room_id.clone(), rooms.len());
+                rooms.push(SavedRoom {
+                    room_id,
+                    events: vec![event],
+                });
+            }
+        }
+    }
+
+    Ok(rooms)
+}
+
+fn load_room(conn: &Connection, room_id: &str) -> rusqlite::Result<Vec<RoomEvent>> {
+    let mut stmt = conn.prepare("SELECT event FROM room_events WHERE room_id = ?1 ORDER BY id")?;
+    let rows = stmt.query_map(params![room_id], |row| row.get::<_, String>(0))?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        let text = row?;
+        match serde_json::from_str::<RoomEvent>(&text) {
+            Ok(event) => events.push(event),
+            Err(e) => {
+                tracing::warn!("Skipping unreadable event for room {}: {}", room_id, e);
+            }
+        }
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,736 @@
+use futures::future::join_all;
+use serde::Serialize;
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{broadcast, watch, RwLock};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use shared::net::{RolePreference, RoomInfo, RoomQuery, ServerMsg};
+
+use crate::archive::ArchiveEvent;
+use crate::auth::hash_password;
+use crate::net::outbox::Outbox;
+use crate::player::Identity;
+use crate::rating::Ratings;
+use crate::room::actor::{
+    restore_room, spawn_room, validate_room_name, RoomCmd, RoomConfig, RoomDetails, RoomHandle,
+    RoomServices,
+};
+use crate::room::journal::SavedRoom;
+
+#[derive(Clone)]
+pub struct RoomManager {
+    rooms: Arc<RwLock<HashMap<String, RoomHandle>>>,
+    lobby_tx: broadcast::Sender<ServerMsg>,
+    next_seq: Arc<AtomicU64>,
+    services: RoomServices,
+    shutdown_tx: Arc<watch::Sender<Option<ServerMsg>>>,
+    maintenance: Arc<AtomicBool>,
+}
+
+pub struct Joiner {
+    pub client_id: Uuid,
+    pub identity: Identity,
+    pub client_tx: Outbox,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminRoom {
+    #[serde(flatten)]
+    pub info: RoomInfo,
+    pub pending_commands: usize,
+    pub responding: bool,
+    pub details: Option<RoomDetails>,
+}
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+const INVITE_CODE_LEN: usize = 6;
+const INVITE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(1);
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_ROOM_RESTARTS: u32 = 3;
+const ROOM_CRASHED: &str = "The room hit an internal error";
+
+impl RoomManager {
+    pub fn new(services: RoomServices) -> Self {
+        let (lobby_tx, _) = broadcast::channel(256);
+        Self {
+            rooms: Arc::new(RwLock::new(HashMap::new())),
+            lobby_tx,
+            next_seq: Arc::new(AtomicU64::new(0)),
+            services,
+            shutdown_tx: Arc::new(watch::channel(None).0),
+            maintenance: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn begin_shutdown(&self, reason: String, retry_after: u64) {
+        tracing::info!("Shutting down: {}", reason);
+        self.shutdown_tx
+            .send_replace(Some(ServerMsg::ServerShuttingDown {
+                reason,
+                retry_after,
+            }));
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown_tx.borrow().is_some()
+    }
+
+    pub fn set_maintenance(&self, enabled: bool) {
+        tracing::info!(
+            "Maintenance mode {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+        self.maintenance.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::Relaxed)
+    }
+
+    pub fn subscribe_shutdown(&self) -> watch::Receiver<Option<ServerMsg>> {
+        self.shutdown_tx.subscribe()
+    }
+
+    pub async fn restore_rooms(&self, saved: Vec<SavedRoom>) {
+        let mut restored = 0;
+
+        for SavedRoom { room_id, events } in saved {
+            let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+            let Some((handle, task)) = restore_room(
+                room_id.clone(),
+                seq,
+                events,
+                Vec::new(),
+                self.services.clone(),
+            ) else {
+                self.services.journal.forget(&room_id);
+                continue;
+            };
+
+            self.rooms.write().await.insert(room_id, handle.clone());
+            self.supervise(handle, task);
+            restored += 1;
+        }
+
+        if restored > 0 {
+            tracing::info!("Restored {} in-progress rooms", restored);
+        }
+    }
+
+    pub fn subscribe_lobby(&self) -> broadcast::Receiver<ServerMsg> {
+        self.lobby_tx.subscribe()
+    }
+
+    pub async fn list_rooms(&self) -> Vec<RoomInfo> {
+        let rooms = self.rooms.read().await;
+        let mut handles: Vec<&RoomHandle> = rooms.values().filter(|h| !h.config.private).collect();
+        handles.sort_by_key(|h| h.seq);
+        handles.into_iter().map(RoomHandle::info).collect()
+    }
+
+    pub async fn query_rooms(
+        &self,
+        query: &RoomQuery,
+    ) -> Result<(Vec<RoomInfo>, Option<String>), String> {
+        let rooms = self.rooms.read().await;
+        let listed = rooms
+            .values()
+            .filter(|h| !h.config.private)
+            .map(|h| (h.seq, h.info()));
+        page_rooms(listed, query)
+    }
+
+    pub async fn admin_rooms(&self) -> Vec<AdminRoom> {
+        let mut handles: Vec<RoomHandle> = self.rooms.read().await.values().cloned().collect();
+        handles.sort_by_key(|h| h.seq);
+
+        let replies = handles.iter().map(|h| {
+            let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+            let sent = h.try_send(RoomCmd::Inspect { reply: reply_tx });
+            async move {
+                if !sent {
+                    return None;
+                }
+                tokio::time::timeout(INSPECT_TIMEOUT, reply_rx)
+                    .await
+                    .ok()
+                    .and_then(Result::ok)
+            }
+        });
+        let details = join_all(replies).await;
+
+        handles
+            .into_iter()
+            .zip(details)
+            .map(|(h, details)| AdminRoom {
+                info: h.info(),
+                pending_commands: h.pending_commands(),
+                responding: details.is_some(),
+                details,
+            })
+            .collect()
+    }
+
+    pub async fn close_room(&self, room_id: &str, reason: String) -> Result<(), String> {
+        let handle = {
+            let rooms = self.rooms.read().await;
+            rooms
+                .get(room_id)
+                .cloned()
+                .ok_or_else(|| "Room not found".to_string())?
+        };
+
+        let close = RoomCmd::Close {
+            reason: reason.clone(),
+        };
+        if let Ok(Ok(())) = tokio::time::timeout(CLOSE_TIMEOUT, handle.send(close)).await {
+            return Ok(());
+        }
+
+        if handle.is_closed() {
+            tracing::warn!("Room {} has already stopped, dropping it", room_id);
+        } else {
+            tracing::warn!("Room {} is not responding, aborting it", room_id);
+            handle.abort();
+            for (_, tx) in handle.members() {
+                let _ = tx.send(ServerMsg::RoomClosed {
+                    room_id: room_id.to_string(),
+                    reason: reason.clone(),
+                });
+            }
+        }
+        self.drop_room(&handle).await;
+        self.services.journal.forget(room_id);
+        Ok(())
+    }
+
+    pub async fn room_states(&self) -> HashMap<&'static str, i64> {
+        let rooms = self.rooms.read().await;
+        let mut states = HashMap::from([("waiting", 0), ("playing", 0), ("finished", 0)]);
+        for h in rooms.values() {
+            let snap = h.snapshot_rx.borrow();
+            let state = match (snap.started, snap.finished) {
+                (false, _) => "waiting",
+                (true, false) => "playing",
+                (true, true) => "finished",
+            };
+            *states.entry(state).or_default() += 1;
+        }
+        states
+    }
+
+    pub async fn queue_depths(&self) -> Vec<usize> {
+        let rooms = self.rooms.read().await;
+        rooms.values().map(RoomHandle::pending_commands).collect()
+    }
+
+    pub async fn get_room(&self, room_id: &str) -> Option<RoomInfo> {
+        let rooms = self.rooms.read().await;
+        rooms
+            .get(room_id)
+            .filter(|h| !h.config.private)
+            .map(RoomHandle::info)
+    }
+
+    pub async fn find_by_code(&self, code: &str) -> Option<String> {
+        let rooms = self.rooms.read().await;
+        rooms
+            .values()
+            .find(|h| {
+                h.invite_code
+                    .as_deref()
+                    .is_some_and(|c| c.eq_ignore_ascii_case(code.trim()))
+            })
+            .map(|h| h.room_id.clone())
+    }
+
+    pub async fn create_room(
+        &self,
+        name: String,
+        mut config: RoomConfig,
+    ) -> Result<RoomInfo, String> {
+        if self.is_shutting_down() {
+            return Err("Server is shutting down".to_string());
+        }
+        if self.in_maintenance() {
+            return Err("Server is in maintenance mode, new rooms are disabled".to_string());
+        }
+        let name = validate_room_name(&name)?;
+        config.validate()?;
+        if let Some(password) = config.password.take() {
+            let hash = tokio::task::spawn_blocking(move || hash_password(&password))
+                .await
+                .map_err(|e| e.to_string())??;
+            config.password_hash = Some(hash);
+        }
+
+        let room_id = Uuid::new_v4().to_string();
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.services.archive.record(ArchiveEvent::RoomCreated {
+            room_id: room_id.clone(),
+            name: name.clone(),
+            config: serde_json::to_string(&config).unwrap_or_default(),
+        });
+
+        let mut rooms = self.rooms.write().await;
+        let invite_code = config.private.then(|| loop {
+            let code = invite_code();
+            if !rooms
+                .values()
+                .any(|h| h.invite_code.as_deref() == Some(code.as_str()))
+            {
+                break code;
+            }
+        });
+
+        let (handle, task) = spawn_room(
+            room_id.clone(),
+            name,
+            config,
+            seq,
+            invite_code,
+            self.services.clone(),
+        );
+        let info = handle.info();
+        rooms.insert(room_id, handle.clone());
+        drop(rooms);
+
+        if !handle.config.private {
+            let _ = self
+                .lobby_tx
+                .send(ServerMsg::RoomAdded { room: info.clone() });
+        }
+        self.supervise(handle, task);
+
+        Ok(info)
+    }
+
+    fn watch_room(&self, handle: RoomHandle) {
+        let manager = self.clone();
+        let mut snapshot_rx = handle.snapshot_rx.clone();
+
+        tokio::spawn(async move {
+            while snapshot_rx.changed().await.is_ok() {
+                if !handle.config.private {
+                    let _ = manager.lobby_tx.send(ServerMsg::RoomChanged {
+                        room: handle.info(),
+                    });
+                }
+            }
+
+            manager.drop_room(&handle).await;
+        });
+    }
+
+    fn supervise(&self, handle: RoomHandle, task: JoinHandle<()>) {
+        self.watch_room(handle.clone());
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            let (mut handle, mut task) = (handle, task);
+            let mut restarts = 0;
+
+            loop {
+                let payload = match task.await {
+                    Ok(()) => return,
+                    Err(e) if e.is_panic() => e.into_panic(),
+                    Err(e) => {
+                        tracing::warn!("Room task {} was cancelled: {}", handle.room_id, e);
+                        return;
+                    }
+                };
+
+                let members = handle.members();
+                tracing::error!(
+                    "Room {} ({}) panicked with {} connected players: {}",
+                    handle.room_id,
+                    handle.name,
+                    members.len(),
+                    panic_message(&*payload)
+                );
+                manager.services.metrics.room_crashes.inc();
+                manager.drop_room(&handle).await;
+
+                for (_, tx) in &members {
+                    let _ = tx.send(ServerMsg::Error {
+                        message: ROOM_CRASHED.to_string(),
+                    });
+                }
+
+                let restored = if restarts < MAX_ROOM_RESTARTS {
+                    manager.recover_room(&handle, members.clone()).await
+                } else {
+                    None
+                };
+                let Some((next, next_task)) = restored else {
+                    tracing::warn!("Room {} could not be restored, closing it", handle.room_id);
+                    manager.services.journal.forget(&handle.room_id);
+                    for (_, tx) in members {
+                        let _ = tx.send(ServerMsg::RoomClosed {
+                            room_id: handle.room_id.clone(),
+                            reason: ROOM_CRASHED.to_string(),
+                        });
+                    }
+                    return;
+                };
+
+                restarts += 1;
+                manager
+                    .rooms
+                    .write()
+                    .await
+                    .insert(next.room_id.clone(), next.clone());
+                if !next.config.private {
+                    let _ = manager
+                        .lobby_tx
+                        .send(ServerMsg::RoomAdded { room: next.info() });
+                }
+                manager.watch_room(next.clone());
+
+                handle = next;
+                task = next_task;
+            }
+        });
+    }
+
+    async fn recover_room(
+        &self,
+        handle: &RoomHandle,
+        members: Vec<(Uuid, Outbox)>,
+    ) -> Option<(RoomHandle, JoinHandle<()>)> {
+        let room_id = handle.room_id.clone();
+        let seq = handle.seq;
+        let services = self.services.clone();
+
+        let (next, task) = tokio::task::spawn_blocking(move || {
+            let events = services.journal.load(&room_id, RESTORE_TIMEOUT)?;
+            restore_room(room_id, seq, events, members, services)
+        })
+        .await
+        .ok()
+        .flatten()?;
+
+        let (before, after) = (handle.info(), next.info());
+        if before.locked != after.locked
+            || before.private != after.private
+            || before.invite_code != after.invite_code
+        {
+            tracing::error!(
+                "Room {} lost its access settings when restored, closing it",
+                handle.room_id
+            );
+            task.abort();
+            return None;
+        }
+        Some((next, task))
+    }
+
+    async fn drop_room(&self, handle: &RoomHandle) {
+        let mut rooms = self.rooms.write().await;
+        if !rooms
+            .get(&handle.room_id)
+            .is_some_and(|h| h.is_same(handle))
+        {
+            return;
+        }
+        rooms.remove(&handle.room_id);
+        drop(rooms);
+
+        if !handle.config.private {
+            let _ = self.lobby_tx.send(ServerMsg::RoomRemoved {
+                room_id: handle.room_id.clone(),
+            });
+        }
+    }
+
+    pub async fn join_room(
+        &self,
+        room_id: &str,
+        joiner: Joiner,
+        role: Option<RolePreference>,
+        secret: Option<&str>,
+    ) -> Result<(), String> {
+        let Joiner {
+            client_id,
+            identity,
+            client_tx,
+        } = joiner;
+        let handle = {
+            let rooms = self.rooms.read().await;
+            rooms
+                .get(room_id)
+                .cloned()
+                .ok_or_else(|| "Room not found".to_string())?
+        };
+        let (check, secret) = (handle.clone(), secret.map(str::to_string));
+        tokio::task::spawn_blocking(move || check.authorize(secret.as_deref()))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        let ratings = if handle.config.vs_bot {
+            self.services
+                .archive
+                .get_ratings(identity.player_id.to_string())
+                .await
+                .unwrap_or_default()
+        } else {
+            Ratings::default()
+        };
+
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+        handle
+            .send(RoomCmd::Join {
+                client_id,
+                identity,
+                ratings,
+                role,
+                client_tx,
+                reply: reply_tx,
+            })
+            .await?;
+
+        reply_rx
+            .await
+            .map_err(|_| "Room did not reply".to_string())?
+    }
+
+    pub async fn rejoin_room(
+        &self,
+        room_id: &str,
+        client_id: Uuid,
+        identity: Identity,
+        token: String,
+        client_tx: Outbox,
+    ) -> Result<(), String> {
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+        self.send_cmd(
+            room_id,
+            RoomCmd::Rejoin {
+                client_id,
+                identity,
+                token,
+                client_tx,
+                reply: reply_tx,
+            },
+        )
+        .await?;
+
+        reply_rx
+            .await
+            .map_err(|_| "Room did not reply".to_string())?
+    }
+
+    pub async fn disconnect(&self, room_id: &str, client_id: Uuid) -> Result<(), String> {
+        self.send_cmd(room_id, RoomCmd::Disconnect { client_id })
+            .await
+    }
+
+    pub async fn leave_room(&self, room_id: &str, client_id: Uuid) -> Result<(), String> {
+        self.send_cmd(room_id, RoomCmd::Leave { client_id }).await
+    }
+
+    pub async fn send_cmd(&self, room_id: &str, cmd: RoomCmd) -> Result<(), String> {
+        let handle = {
+            let rooms = self.rooms.read().await;
+            rooms
+                .get(room_id)
+                .cloned()
+                .ok_or_else(|| "Room not found".to_string())?
+        };
+
+        handle.send(cmd).await
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+fn invite_code() -> String {
+    Uuid::new_v4()
+        .as_bytes()
+        .iter()
+        .take(INVITE_CODE_LEN)
+        .map(|b| INVITE_ALPHABET[*b as usize % INVITE_ALPHABET.len()] as char)
+        .collect()
+}
+
+fn page_rooms(
+    rooms: impl Iterator<Item = (u64, RoomInfo)>,
+    query: &RoomQuery,
+) -> Result<(Vec<RoomInfo>, Option<String>), String> {
+    let after = query
+        .cursor
+        .as_deref()
+        .map(|c| c.parse::<u64>().map_err(|_| "Invalid cursor".to_string()))
+        .transpose()?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut matching: Vec<(u64, RoomInfo)> = rooms
+        .filter(|(seq, _)| after.is_none_or(|a| *seq > a))
+        .filter(|(_, info)| room_matches(query, info))
+        .collect();
+    matching.sort_by_key(|(seq, _)| *seq);
+
+    let next_cursor = if matching.len() > limit {
+        Some(matching[limit - 1].0.to_string())
+    } else {
+        None
+    };
+
+    let page = matching
+        .into_iter()
+        .take(limit)
+        .map(|(_, info)| info)
+        .collect();
+
+    Ok((page, next_cursor))
+}
+
+fn room_matches(query: &RoomQuery, info: &RoomInfo) -> bool {
+    let capacity = if info.vs_bot { 1 } else { 2 };
+
+    if query.open_only && (info.started || info.players >= capacity) {
+        return false;
+    }
+    if query.vs_bot.is_some_and(|v| v != info.vs_bot) {
+        return false;
+    }
+    if query.radius.is_some_and(|r| r != info.radius) {
+        return false;
+    }
+    if query.started.is_some_and(|s| s != info.started) {
+        return false;
+    }
+    if let Some(needle) = query.name_contains.as_deref() {
+        if !info.name.to_lowercase().contains(&needle.to_lowercase()) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::types::{AbandonPolicy, TimeoutPolicy};
+
+    fn info(name: &str, players: u8, vs_bot: bool) -> RoomInfo {
+        RoomInfo {
+            room_id: name.to_string(),
+            name: name.to_string(),
+            players,
+            trapper: None,
+            mouse: None,
+            vs_bot,
+            started: false,
+            radius: 6,
+            time_control: None,
+            on_timeout: TimeoutPolicy::default(),
+            on_abandon: AbandonPolicy::default(),
+            private: false,
+            locked: false,
+            invite_code: None,
+        }
+    }
+
+    fn rooms(count: u64) -> Vec<(u64, RoomInfo)> {
+        (1..=count)
+            .rev()
+            .map(|seq| (seq, info(&format!("room-{}", seq), 0, false)))
+            .collect()
+    }
+
+    fn page(
+        rooms: &[(u64, RoomInfo)],
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> (Vec<String>, Option<String>) {
+        let query = RoomQuery {
+            cursor: cursor.map(str::to_string),
+            limit: Some(limit),
+            ..RoomQuery::default()
+        };
+        let (page, next) = page_rooms(rooms.iter().cloned(), &query).unwrap();
+        (page.into_iter().map(|r| r.name).collect(), next)
+    }
+
+    #[test]
+    fn pages_follow_the_cursor_in_creation_order() {
+        let rooms = rooms(5);
+
+        let (names, next) = page(&rooms, None, 2);
+        assert_eq!(names, ["room-1", "room-2"]);
+        assert_eq!(next.as_deref(), Some("2"));
+
+        let (names, next) = page(&rooms, next.as_deref(), 2);
+        assert_eq!(names, ["room-3", "room-4"]);
+        assert_eq!(next.as_deref(), Some("4"));
+
+        let (names, next) = page(&rooms, next.as_deref(), 2);
+        assert_eq!(names, ["room-5"]);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn a_full_last_page_has_no_cursor() {
+        let rooms = rooms(4);
+
+        let (names, next) = page(&rooms, Some("2"), 2);
+        assert_eq!(names, ["room-3", "room-4"]);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn a_malformed_cursor_is_rejected() {
+        let query = RoomQuery {
+            cursor: Some("abc".to_string()),
+            ..RoomQuery::default()
+        };
+        assert!(page_rooms(rooms(3).into_iter(), &query).is_err());
+    }
+
+    #[test]
+    fn open_only_skips_full_and_started_rooms() {
+        let query = RoomQuery {
+            open_only: true,
+            ..RoomQuery::default()
+        };
+        let mut started = info("started", 1, false);
+        started.started = true;
+
+        assert!(room_matches(&query, &info("waiting", 1, false)));
+        assert!(!room_matches(&query, &info("full", 2, false)));
+        assert!(!room_matches(&query, &info("bot", 1, true)));
+        assert!(!room_matches(&query, &started));
+    }
+
+    #[test]
+    fn name_filter_ignores_case() {
+        let query = RoomQuery {
+            name_contains: Some("LOBBY".to_string()),
+            ..RoomQuery::default()
+        };
+        assert!(room_matches(&query, &info("Friday lobby", 0, false)));
+        assert!(!room_matches(&query, &info("Friday game", 0, false)));
+    }
+}
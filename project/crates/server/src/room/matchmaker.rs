@@ -0,0 +1,407 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::time::{interval, Instant, MissedTickBehavior};
+use uuid::Uuid;
+
+use shared::net::{RolePreference, ServerMsg};
+use shared::types::{AbandonPolicy, TimeoutPolicy};
+
+use crate::archive::Archive;
+use crate::net::outbox::Outbox;
+use crate::player::Identity;
+use crate::room::actor::RoomConfig;
+use crate::room::manager::{Joiner, RoomManager};
+
+const TICK: Duration = Duration::from_secs(1);
+const BOT_OFFER_AFTER: Duration = Duration::from_secs(30);
+const BASE_RATING_WINDOW: f64 = 100.0;
+const RATING_WINDOW_PER_SEC: f64 = 10.0;
+
+pub struct Ticket {
+    pub client_id: Uuid,
+    pub identity: Identity,
+    pub preferred_role: Option<RolePreference>,
+    pub radius: i32,
+    pub client_tx: Outbox,
+    pub matched_tx: mpsc::UnboundedSender<String>,
+}
+
+struct Entry {
+    ticket: Ticket,
+    rating: Option<f64>,
+    since: Instant,
+    bot_offered: bool,
+}
+
+enum MatchCmd {
+    Enqueue { ticket: Ticket, rating: Option<f64> },
+    Cancel { client_id: Uuid },
+    AcceptBot { client_id: Uuid, client_tx: Outbox },
+}
+
+#[derive(Clone)]
+pub struct Matchmaker {
+    cmd_tx: mpsc::UnboundedSender<MatchCmd>,
+    manager: RoomManager,
+    archive: Archive,
+}
+
+impl Matchmaker {
+    pub fn spawn(manager: RoomManager, archive: Archive) -> Self {
+        let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
+        tokio::spawn(matchmaker_loop(manager.clone(), cmd_rx));
+        Self {
+            cmd_tx,
+            manager,
+            archive,
+        }
+    }
+
+    pub async fn enqueue(&self, ticket: Ticket) -> Result<(), String> {
+        if self.manager.is_shutting_down() {
+            return Err("Server is shutting down".to_string());
+        }
+        if self.manager.in_maintenance() {
+            return Err("Server is in maintenance mode, new games are disabled".to_string());
+        }
+
+        let profile = self
+            .archive
+            .get_profile(ticket.identity.player_id.to_string())
+            .await
+            .ok()
+            .flatten();
+        let rating = profile.map(|p| {
+            let (t, m) = (p.trapper_rating as f64, p.mouse_rating as f64);
+            match ticket.preferred_role {
+                Some(RolePreference::Trapper) => t,
+                Some(RolePreference::Mouse) => m,
+                _ => (t + m) / 2.0,
+            }
+        });
+
+        self.cmd_tx
+            .send(MatchCmd::Enqueue { ticket, rating })
+            .map_err(|_| "Matchmaker is not running".to_string())
+    }
+
+    pub fn cancel(&self, client_id: Uuid) {
+        let _ = self.cmd_tx.send(MatchCmd::Cancel { client_id });
+    }
+
+    pub fn accept_bot(&self, client_id: Uuid, client_tx: Outbox) -> Result<(), String> {
+        self.cmd_tx
+            .send(MatchCmd::AcceptBot {
+                client_id,
+                client_tx,
+            })
+            .map_err(|_| "Matchmaker is not running".to_string())
+    }
+}
+
+async fn matchmaker_loop(manager: RoomManager, mut cmd_rx: mpsc::UnboundedReceiver<MatchCmd>) {
+    let mut queue: Vec<Entry> = Vec::new();
+    let mut tick = interval(TICK);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        tokio::select! {
+            cmd = cmd_rx.recv() => {
+                let Some(cmd) = cmd else {
+                    break;
+                };
+
+                match cmd {
+                    MatchCmd::Enqueue { ticket, rating } => {
+                        queue.retain(|e| e.ticket.client_id != ticket.client_id);
+                        let _ = ticket.client_tx.send(ServerMsg::Queued {
+                            players_waiting: queue.len(),
+                        });
+                        queue.push(Entry {
+                            ticket,
+                            rating,
+                            since: Instant::now(),
+                            bot_offered: false,
+                        });
+                    }
+
+                    MatchCmd::Cancel { client_id } => {
+                        queue.retain(|e| e.ticket.client_id != client_id);
+                    }
+
+                    MatchCmd::AcceptBot { client_id, client_tx } => {
+                        match queue.iter().position(|e| e.ticket.client_id == client_id) {
+                            Some(i) => {
+                                let entry = queue.remove(i);
+                                start_bot_game(&manager, entry.ticket).await;
+                            }
+                            None => {
+                                let _ = client_tx.send(ServerMsg::Error {
+                                    message: "Not in the quick play queue".to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+
+            _ = tick.tick() => {
+                queue.retain(|e| !e.ticket.client_tx.is_closed());
+                offer_bots(&mut queue);
+            }
+        }
+
+        while let Some((i, j)) = find_pair(&queue) {
+            let b = queue.remove(j);
+            let a = queue.remove(i);
+            if let Some(left) = start_match(&manager, a, b).await {
+                let at = queue.partition_point(|e| e.since <= left.since);
+                queue.insert(at, left);
+            }
+        }
+    }
+}
+
+fn offer_bots(queue: &mut [Entry]) {
+    let now = Instant::now();
+    for e in queue.iter_mut() {
+        if e.bot_offered || now.duration_since(e.since) < BOT_OFFER_AFTER {
+            continue;
+        }
+        e.bot_offered = true;
+        let _ = e.ticket.client_tx.send(ServerMsg::BotGameOffered {
+            waited_secs: now.duration_since(e.since).as_secs(),
+        });
+    }
+}
+
+fn roles_compatible(a: Option<RolePreference>, b: Option<RolePreference>) -> bool {
+    !matches!(
+        (a, b),
+        (Some(RolePreference::Trapper), Some(RolePreference::Trapper))
+            | (Some(RolePreference::Mouse), Some(RolePreference::Mouse))
+    )
+}
+
+fn find_pair(queue: &[Entry]) -> Option<(usize, usize)> {
+    let now = Instant::now();
+
+    for (i, a) in queue.iter().enumerate() {
+        let mut best: Option<(usize, f64)> = None;
+
+        for (j, b) in queue.iter().enumerate().skip(i + 1) {
+            if a.ticket.radius != b.ticket.radius
+                || !roles_compatible(a.ticket.preferred_role, b.ticket.preferred_role)
+            {
+                continue;
+            }
+
+            let diff = match (a.rating, b.rating) {
+                (Some(ra), Some(rb)) => {
+                    let waited = now.duration_since(a.since.min(b.since)).as_secs_f64();
+                    if (ra - rb).abs() > BASE_RATING_WINDOW + RATING_WINDOW_PER_SEC * waited {
+                        continue;
+                    }
+                    (ra - rb).abs()
+                }
+                _ => 0.0,
+            };
+
+            if best.is_none_or(|(_, d)| diff < d) {
+                best = Some((j, diff));
+            }
+        }
+
+        if let Some((j, _)) = best {
+            return Some((i, j));
+        }
+    }
+
+    None
+}
+
+fn quick_config(radius: i32, vs_bot: bool) -> RoomConfig {
+    RoomConfig {
+        vs_bot,
+        radius,
+        time_control: None,
+        on_timeout: TimeoutPolicy::default(),
+        on_abandon: AbandonPolicy::default(),
+        private: false,
+        password: None,
+        password_hash: None,
+        invited_player: None,
+    }
+}
+
+async fn start_match(manager: &RoomManager, a: Entry, b: Entry) -> Option<Entry> {
+    let concrete = |e: &Entry| {
+        matches!(
+            e.ticket.preferred_role,
+            Some(RolePreference::Trapper) | Some(RolePreference::Mouse)
+        )
+    };
+    let (first, second) = if concrete(&a) || !concrete(&b) {
+        (a, b)
+    } else {
+        (b, a)
+    };
+
+    let first = first.ticket;
+    let name = format!("{} vs {}", first.identity.name, second.ticket.identity.name);
+    let room_id = match manager
+        .create_room(name, quick_config(first.radius, false))
+        .await
+    {
+        Ok(info) => info.room_id,
+        Err(e) => {
+            for t in [&first, &second.ticket] {
+                let _ = t.client_tx.send(ServerMsg::Error { message: e.clone() });
+            }
+            return None;
+        }
+    };
+
+    tracing::info!(
+        "Matched {} and {} in room {}",
+        first.identity.name,
+        second.ticket.identity.name,
+        room_id
+    );
+
+    let role = first.preferred_role;
+    if !seat(manager, &room_id, first, role).await {
+        let reason = "The match could not be started".to_string();
+        let _ = manager.close_room(&room_id, reason).await;
+        return Some(second);
+    }
+    seat(manager, &room_id, second.ticket, None).await;
+    None
+}
+
+async fn start_bot_game(manager: &RoomManager, t: Ticket) {
+    let name = format!("{} vs bot", t.identity.name);
+    match manager
+        .create_room(name, quick_config(t.radius, true))
+        .await
+    {
+        Ok(info) => {
+            let role = t.preferred_role;
+            seat(manager, &info.room_id, t, role).await;
+        }
+        Err(message) => {
+            let _ = t.client_tx.send(ServerMsg::Error { message });
+        }
+    }
+}
+
+async fn seat(
+    manager: &RoomManager,
+    room_id: &str,
+    t: Ticket,
+    role: Option<RolePreference>,
+) -> bool {
+    let _ = t.client_tx.send(ServerMsg::MatchFound {
+        room_id: room_id.to_string(),
+    });
+
+    match manager
+        .join_room(
+            room_id,
+            Joiner {
+                client_id: t.client_id,
+                identity: t.identity,
+                client_tx: t.client_tx.clone(),
+            },
+            role,
+            None,
+        )
+        .await
+    {
+        Ok(()) => {
+            if t.matched_tx.send(room_id.to_string()).is_ok() {
+                return true;
+            }
+            let _ = manager.disconnect(room_id, t.client_id).await;
+        }
+        Err(message) => {
+            let _ = t.client_tx.send(ServerMsg::Error { message });
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(rating: Option<f64>, role: Option<RolePreference>, waited: u64) -> Entry {
+        let client_id = Uuid::new_v4();
+        Entry {
+            ticket: Ticket {
+                client_id,
+                identity: Identity::guest(client_id),
+                preferred_role: role,
+                radius: 6,
+                client_tx: Outbox::detached(),
+                matched_tx: mpsc::unbounded_channel().0,
+            },
+            rating,
+            since: Instant::now() - Duration::from_secs(waited),
+            bot_offered: false,
+        }
+    }
+
+    #[test]
+    fn only_clashing_roles_are_incompatible() {
+        use RolePreference::*;
+        assert!(!roles_compatible(Some(Trapper), Some(Trapper)));
+        assert!(!roles_compatible(Some(Mouse), Some(Mouse)));
+        assert!(roles_compatible(Some(Trapper), Some(Mouse)));
+        assert!(roles_compatible(Some(Random), Some(Trapper)));
+        assert!(roles_compatible(None, None));
+    }
+
+    #[test]
+    fn guests_are_paired_in_arrival_order() {
+        let queue = [
+            entry(None, None, 0),
+            entry(None, None, 0),
+            entry(None, None, 0),
+        ];
+        assert_eq!(find_pair(&queue), Some((0, 1)));
+    }
+
+    #[test]
+    fn clashing_roles_and_board_sizes_are_not_paired() {
+        let trapper = Some(RolePreference::Trapper);
+        let mut other_size = entry(None, None, 0);
+        other_size.ticket.radius = 7;
+
+        assert_eq!(
+            find_pair(&[entry(None, trapper, 0), entry(None, trapper, 0)]),
+            None
+        );
+        assert_eq!(find_pair(&[entry(None, None, 0), other_size]), None);
+    }
+
+    #[test]
+    fn the_closest_rating_is_preferred() {
+        let queue = [
+            entry(Some(1500.0), None, 0),
+            entry(Some(1590.0), None, 0),
+            entry(Some(1510.0), None, 0),
+        ];
+        assert_eq!(find_pair(&queue), Some((0, 2)));
+    }
+
+    #[test]
+    fn the_rating_window_widens_with_waiting_time() {
+        let fresh = [entry(Some(1500.0), None, 0), entry(Some(1750.0), None, 0)];
+        assert_eq!(find_pair(&fresh), None);
+
+        let waiting = [entry(Some(1500.0), None, 20), entry(Some(1750.0), None, 0)];
+        assert_eq!(find_pair(&waiting), Some((0, 1)));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod actor;
+pub mod clock;
+pub mod journal;
+pub mod manager;
+pub mod matchmaker;
@@ -1,5 +1,7 @@
 use crate::hex::{inside_board, is_border, neighbors};
-use crate::types::{Coord, GameState};
+use crate::types::{Action, BotLevel, Coord, GameState, Turn};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::{HashMap, VecDeque};
 
 pub fn choose_mouse_move(s: &GameState) -> Option<Coord> {
@@ -49,3 +51,57 @@ pub fn choose_mouse_move(s: &GameState) -> Option<Coord> {
         .into_iter()
         .find(|n| inside_board(*n, s.cfg.radius) && !s.blocks.contains(n))
 }
+
+pub fn choose_trapper_block(s: &GameState) -> Option<Coord> {
+    if let Some(next) = choose_mouse_move(s) {
+        if !s.blocks.contains(&next) {
+            return Some(next);
+        }
+    }
+
+    let radius = s.cfg.radius;
+    (-radius..=radius)
+        .flat_map(|q| (-radius..=radius).map(move |r| Coord { q, r }))
+        .find(|c| inside_board(*c, radius) && *c != s.mouse && !s.blocks.contains(c))
+}
+
+pub fn choose_action(s: &GameState) -> Option<Action> {
+    match s.turn {
+        Turn::Trapper => choose_trapper_block(s).map(|at| Action::PlaceBlock { at }),
+        Turn::Mouse => choose_mouse_move(s).map(|to| Action::MoveMouse { to }),
+    }
+}
+
+pub fn choose_action_at(s: &GameState, level: BotLevel) -> Option<Action> {
+    let best_chance = match level {
+        BotLevel::Easy => 0.25,
+        BotLevel::Medium => 0.6,
+        BotLevel::Hard => 1.0,
+    };
+
+    let mut rng = rand::thread_rng();
+    if rng.gen_bool(best_chance) {
+        return choose_action(s);
+    }
+    random_action(s, &mut rng).or_else(|| choose_action(s))
+}
+
+fn random_action(s: &GameState, rng: &mut impl Rng) -> Option<Action> {
+    let radius = s.cfg.radius;
+    match s.turn {
+        Turn::Trapper => {
+            let free: Vec<Coord> = (-radius..=radius)
+                .flat_map(|q| (-radius..=radius).map(move |r| Coord { q, r }))
+                .filter(|c| inside_board(*c, radius) && *c != s.mouse && !s.blocks.contains(c))
+                .collect();
+            free.choose(rng).map(|&at| Action::PlaceBlock { at })
+        }
+        Turn::Mouse => {
+            let moves: Vec<Coord> = neighbors(s.mouse)
+                .into_iter()
+                .filter(|n| inside_board(*n, radius) && !s.blocks.contains(n))
+                .collect();
+            moves.choose(rng).map(|&to| Action::MoveMouse { to })
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::types::{Action, GameState, Turn};
+use crate::types::{
+    AbandonPolicy, Action, ClockState, GameState, TimeControl, TimeoutPolicy, Turn,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,34 +8,275 @@ pub struct RoomInfo {
     pub room_id: String,
     pub name: String,
     pub players: u8,
+    pub trapper: Option<String>,
+    pub mouse: Option<String>,
     pub vs_bot: bool,
+    pub started: bool,
+    pub radius: i32,
+    pub time_control: Option<TimeControl>,
+    pub on_timeout: TimeoutPolicy,
+    pub on_abandon: AbandonPolicy,
+    pub private: bool,
+    pub locked: bool,
+    pub invite_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub player_id: String,
+    pub name: String,
+    pub games_played: u32,
+    pub games_won: u32,
+    pub trapper_rating: i32,
+    pub mouse_rating: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub player_id: String,
+    pub name: String,
+    pub rating: i32,
+    pub games: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Limit {
+    MessageSize,
+    MessageRate,
+    RoomName,
+    RoomCreation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RolePreference {
+    Trapper,
+    Mouse,
+    Random,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomQuery {
+    pub open_only: bool,
+    pub vs_bot: Option<bool>,
+    pub radius: Option<i32>,
+    pub name_contains: Option<String>,
+    pub started: Option<bool>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ClientMsg {
-    CreateRoom { name: String, vs_bot: bool },
-    JoinRoom { room_id: String },
+    Login {
+        name: String,
+        #[serde(default)]
+        player_id: Option<String>,
+    },
+    SetNickname {
+        name: String,
+    },
+    GetProfile {
+        player_id: String,
+    },
+    GetLeaderboard {
+        role: Turn,
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    CreateRoom {
+        name: String,
+        vs_bot: bool,
+        #[serde(default)]
+        radius: Option<i32>,
+        #[serde(default)]
+        role: Option<RolePreference>,
+        #[serde(default)]
+        time_control: Option<TimeControl>,
+        #[serde(default)]
+        on_timeout: TimeoutPolicy,
+        #[serde(default)]
+        on_abandon: AbandonPolicy,
+        #[serde(default)]
+        private: bool,
+        #[serde(default)]
+        password: Option<String>,
+        #[serde(default)]
+        invited: Option<String>,
+    },
+    JoinRoom {
+        room_id: String,
+        #[serde(default)]
+        role: Option<RolePreference>,
+        #[serde(default)]
+        password: Option<String>,
+    },
+    JoinByCode {
+        code: String,
+        #[serde(default)]
+        role: Option<RolePreference>,
+    },
+    RejoinRoom {
+        room_id: String,
+        token: String,
+    },
     LeaveRoom,
-    PlayerAction { action: Action },
-    ListRooms,
+    PlayerAction {
+        action: Action,
+    },
+    ListRooms {
+        #[serde(default)]
+        query: RoomQuery,
+    },
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    AbortGame,
+    Ready,
+    UpdateRoom {
+        radius: i32,
+        #[serde(default)]
+        time_control: Option<TimeControl>,
+        #[serde(default)]
+        on_timeout: TimeoutPolicy,
+        #[serde(default)]
+        on_abandon: AbandonPolicy,
+    },
+    SwapRoles,
+    Kick {
+        seat: Turn,
+    },
+    Chat {
+        text: String,
+    },
+    MuteOpponent {
+        muted: bool,
+    },
+    RequestRematch {
+        #[serde(default)]
+        swap_roles: bool,
+    },
+    AcceptRematch,
+    SubscribeLobby,
+    UnsubscribeLobby,
+    QuickPlay {
+        #[serde(default)]
+        preferred_role: Option<RolePreference>,
+        #[serde(default)]
+        board_size: Option<i32>,
+    },
+    CancelQuickPlay,
+    AcceptBotGame,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ServerMsg {
+    Profile {
+        profile: Profile,
+    },
+    Leaderboard {
+        role: Turn,
+        entries: Vec<LeaderboardEntry>,
+    },
     RoomList {
         rooms: Vec<RoomInfo>,
+        next_cursor: Option<String>,
+    },
+    RoomCreated {
+        room: RoomInfo,
+    },
+    RoomAdded {
+        room: RoomInfo,
+    },
+    RoomChanged {
+        room: RoomInfo,
+    },
+    RoomRemoved {
+        room_id: String,
     },
     LobbyState {
         room_id: String,
         players: u8,
+        trapper: Option<String>,
+        mouse: Option<String>,
         vs_bot: bool,
+        host: Option<Turn>,
+        ready: Vec<Turn>,
+        radius: i32,
+        time_control: Option<TimeControl>,
+        on_timeout: TimeoutPolicy,
+        on_abandon: AbandonPolicy,
+    },
+    Kicked {
+        room_id: String,
     },
     GameStart {
         state: GameState,
         your_role: Turn,
+        trapper: String,
+        mouse: String,
+        clock: Option<ClockState>,
+        rejoin_token: String,
     },
     GameUpdate {
         state: GameState,
+        clock: Option<ClockState>,
+    },
+    AutoMoved {
+        side: Turn,
+        action: Action,
+    },
+    OpponentDisconnected {
+        grace_secs: u64,
+    },
+    OpponentReturned,
+    BotTookOver {
+        side: Turn,
+    },
+    DrawOffered,
+    RematchOffered {
+        swap_roles: bool,
+    },
+    SeriesScore {
+        you: u32,
+        opponent: u32,
+    },
+    Latency {
+        rtt_ms: u64,
+    },
+    Chat {
+        from: String,
+        seat: Turn,
+        text: String,
+        sent_at: u64,
+    },
+    Queued {
+        players_waiting: usize,
+    },
+    MatchFound {
+        room_id: String,
+    },
+    BotGameOffered {
+        waited_secs: u64,
+    },
+    ServerShuttingDown {
+        reason: String,
+        retry_after: u64,
+    },
+    Announcement {
+        message: String,
+    },
+    RoomClosed {
+        room_id: String,
+        reason: String,
+    },
+    Disconnected {
+        reason: String,
+    },
+    LimitExceeded {
+        limit: Limit,
+        message: String,
     },
     Error {
         message: String,
@@ -2,6 +2,10 @@ use crate::hex::{inside_board, is_border, neighbors};
 use crate::types::{Action, GameState, GameStatus, Turn};
 use thiserror::Error;
 
+pub const DEFAULT_RADIUS: i32 = 6;
+pub const MIN_RADIUS: i32 = 3;
+pub const MAX_RADIUS: i32 = 10;
+
 #[derive(Debug, Error)]
 pub enum GameError {
     #[error("game already ended")]
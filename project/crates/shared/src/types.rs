@@ -17,6 +17,33 @@ pub enum GameStatus {
     Running,
     TrapperWon,
     MouseWon,
+    Resigned { by: Turn },
+    Draw,
+    Aborted,
+    TimeOut { by: Turn },
+    Abandoned { by: Turn },
+}
+
+impl Turn {
+    pub fn other(self) -> Turn {
+        match self {
+            Turn::Trapper => Turn::Mouse,
+            Turn::Mouse => Turn::Trapper,
+        }
+    }
+}
+
+impl GameStatus {
+    pub fn winner(&self) -> Option<Turn> {
+        match self {
+            GameStatus::TrapperWon => Some(Turn::Trapper),
+            GameStatus::MouseWon => Some(Turn::Mouse),
+            GameStatus::Resigned { by }
+            | GameStatus::TimeOut { by }
+            | GameStatus::Abandoned { by } => Some(by.other()),
+            GameStatus::Running | GameStatus::Draw | GameStatus::Aborted => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +53,41 @@ pub struct BoardConfig {
     pub seed: u64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeControl {
+    Clock { base_secs: u64, increment_secs: u64 },
+    PerMove { secs: u64 },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BotLevel {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeoutPolicy {
+    #[default]
+    Lose,
+    AutoMove,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AbandonPolicy {
+    #[default]
+    Forfeit,
+    BotTakeover,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockState {
+    pub trapper_ms: u64,
+    pub mouse_ms: u64,
+    pub ticking: Option<Turn>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
     pub cfg: BoardConfig,